@@ -0,0 +1,128 @@
+//! Moving destructors off the audio thread.
+//!
+//! Dropping a value may deallocate, run arbitrary user code, or take locks.  The audio thread instead pushes boxed
+//! values to a [DeferredDropSender], and some other thread periodically calls [DeferredDropBin::drain] to actually drop
+//! them.
+use std::any::Any;
+use std::num::NonZeroUsize;
+
+use crate::mpsc_ring::{mpsc_ring, MpscRingReceiver, MpscRingSender};
+
+// The ring wants Copy values, so boxes travel through it as raw pointers.  Ownership is handed off exactly once: the
+// sender converts with `Box::into_raw` only when the push succeeds, and the bin converts back with `Box::from_raw` once
+// per pop.  The fixed-capacity ring is used rather than the SPSC queue because pushing must never allocate.
+#[derive(Copy, Clone)]
+struct ErasedBox(*mut (dyn Any + Send));
+
+// The pointee is Send, and the pointer is only ever dereferenced by whoever pops it.
+unsafe impl Send for ErasedBox {}
+
+/// The pushing half of a [deferred_drop_bin], to be used from the audio thread.
+///
+/// Senders may be cloned, for example to hand one to each of several audio-side objects.
+#[derive(Clone)]
+pub struct DeferredDropSender {
+    ring: MpscRingSender<ErasedBox>,
+}
+
+/// The draining half of a [deferred_drop_bin].
+///
+/// Dropping the bin drains it.  Anything pushed after that is leaked.
+pub struct DeferredDropBin {
+    ring: MpscRingReceiver<ErasedBox>,
+}
+
+/// Construct a bin which can hold at least `capacity` values awaiting drop.
+pub fn deferred_drop_bin(capacity: NonZeroUsize) -> (DeferredDropSender, DeferredDropBin) {
+    let (sender, receiver) = mpsc_ring(capacity);
+    (
+        DeferredDropSender { ring: sender },
+        DeferredDropBin { ring: receiver },
+    )
+}
+
+impl DeferredDropSender {
+    /// Hand a value off to be dropped on the draining thread.
+    ///
+    /// Never allocates, blocks, or runs a destructor.  Any `Box<T>` with `T: Send + 'static` converts to the argument
+    /// without allocating.  If the bin is full the value is handed back, and it is up to the caller to hold onto it and
+    /// try again later rather than dropping it.
+    pub fn push(&self, value: Box<dyn Any + Send>) -> Result<(), Box<dyn Any + Send>> {
+        let ptr = Box::into_raw(value);
+        self.ring.try_push(ErasedBox(ptr)).map_err(|e| {
+            // Safety: the push failed, so we still own this pointer.
+            unsafe { Box::from_raw(e.0) }
+        })
+    }
+}
+
+impl DeferredDropBin {
+    /// Drop everything which has been pushed so far, returning how many values were dropped.
+    pub fn drain(&mut self) -> usize {
+        let mut count = 0;
+        while let Some(p) = self.ring.pop() {
+            // Safety: every pointer in the ring came from `Box::into_raw` and is popped exactly once.
+            drop(unsafe { Box::from_raw(p.0) });
+            count += 1;
+        }
+        count
+    }
+}
+
+impl Drop for DeferredDropBin {
+    fn drop(&mut self) {
+        self.drain();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::sync::{Arc, AtomicUsize, Ordering};
+
+    /// Counts how many times values holding the same counter have been dropped.
+    struct DropCounter(Arc<AtomicUsize>);
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn test_drops_on_drain() {
+        crate::sync::wrap_test(test_drops_on_drain_inner);
+    }
+
+    fn test_drops_on_drain_inner() {
+        let drops = Arc::new(AtomicUsize::new(0));
+        let (sender, mut bin) = deferred_drop_bin(NonZeroUsize::new(4).unwrap());
+
+        let pusher_drops = drops.clone();
+        let pusher = crate::sync::spawn(move || {
+            for _ in 0..4 {
+                sender
+                    .push(Box::new(DropCounter(pusher_drops.clone())))
+                    .map_err(|_| ())
+                    .unwrap();
+            }
+
+            // The bin is full; the value comes back rather than being dropped here.
+            sender
+                .push(Box::new(DropCounter(pusher_drops.clone())))
+                .unwrap_err()
+        });
+        let rejected = pusher.join().unwrap();
+        // Pushing dropped nothing, including the rejected value.
+        assert_eq!(drops.load(Ordering::Relaxed), 0);
+
+        assert_eq!(bin.drain(), 4);
+        assert_eq!(drops.load(Ordering::Relaxed), 4);
+
+        drop(rejected);
+        assert_eq!(drops.load(Ordering::Relaxed), 5);
+        assert_eq!(bin.drain(), 0);
+        assert_eq!(drops.load(Ordering::Relaxed), 5);
+    }
+}
@@ -0,0 +1,252 @@
+//! A pointer to the latest snapshot of some value, for lock-free handoff from publishers to readers.
+//!
+//! Publishers replace the value with [GenerationalPtr::publish], and readers grab whatever is latest with
+//! [GenerationalPtr::load_latest].  Every published value carries a generation, which increases by one per publish.
+//! Since the generation travels with the value, a reader comparing generations never mistakes a new value which happens
+//! to reuse an old address for the old value.
+//!
+//! Loading never blocks and never allocates or frees, so it is suitable for the audio thread.  Publishing may block
+//! until readers which could still see the old value are done with it, so it belongs on other threads, and the audio
+//! thread shouldn't hold a guard for long.
+use std::marker::PhantomData;
+
+use crate::sync::{fence, spin_loop, AtomicPtr, AtomicUsize, Mutex, Ordering};
+
+// The implementation is a minimal RCU with two reader counts:
+//
+// - Readers read the epoch, increment the reader count for it, then check that the epoch didn't change.  If it did, a
+//   publish raced with them and they retry.  Only after this do they load the pointer.
+// - Publishers swap the pointer, then advance the epoch, then wait for the count of the old epoch to drain.  A reader
+//   counted in the old epoch might have loaded the old pointer.  A reader counted in the new epoch confirmed the epoch
+//   after it advanced, so it loaded the pointer after the swap and can't see the old value.
+// - Once the old count drains, nothing can reach the old value, so it is handed back to the publisher.  Freeing it is
+//   up to them, for example through a deferred drop bin.
+// - Publishes are serialized by a mutex, since two publishers advancing the epoch out of order could each wait for the
+//   wrong count.  Readers never touch it.
+// - The argument above needs the reader's increment and epoch check, and the publisher's epoch advance and wait, to be
+//   totally ordered: otherwise both sides can read the other's counter from before the other's write.  That takes a
+//   SeqCst fence on each side, not just SeqCst accesses.
+
+/// The value and its generation, so that readers always see the two together.
+struct Node<T> {
+    generation: u64,
+    value: Box<T>,
+}
+
+/// A pointer to the latest published value.
+pub struct GenerationalPtr<T> {
+    current: AtomicPtr<Node<T>>,
+    epoch: AtomicUsize,
+    readers: [AtomicUsize; 2],
+
+    /// Serializes publishers, and holds the generation of the current value.
+    publishing: Mutex<u64>,
+
+    _phantom: PhantomData<Box<Node<T>>>,
+}
+
+/// Keeps a value from [GenerationalPtr::load_latest] alive.
+///
+/// Dereferences to the value.  Publishers wait for guards which might see the values they replace, so drop guards
+/// promptly.
+pub struct Guard<'a, T> {
+    ptr: &'a GenerationalPtr<T>,
+    node: *const Node<T>,
+    slot: usize,
+}
+
+impl<T> GenerationalPtr<T> {
+    /// Construct a pointer to `initial`, which has generation 0.
+    pub fn new(initial: Box<T>) -> GenerationalPtr<T> {
+        GenerationalPtr {
+            current: AtomicPtr::new(Box::into_raw(Box::new(Node {
+                generation: 0,
+                value: initial,
+            }))),
+            epoch: AtomicUsize::new(0),
+            readers: [AtomicUsize::new(0), AtomicUsize::new(0)],
+            publishing: Mutex::new(0),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Publish a new value, returning the one it replaced.
+    ///
+    /// Blocks until no reader can still see the old value.  The returned box is then the only reference to it, and may
+    /// be freed however is convenient.
+    pub fn publish(&self, new: Box<T>) -> Box<T> {
+        let mut generation = self.publishing.lock().unwrap();
+        *generation += 1;
+        let node = Box::into_raw(Box::new(Node {
+            generation: *generation,
+            value: new,
+        }));
+
+        let old = self.current.swap(node, Ordering::SeqCst);
+        let old_epoch = self.epoch.fetch_add(1, Ordering::SeqCst);
+        fence(Ordering::SeqCst);
+        while self.readers[old_epoch & 1].load(Ordering::SeqCst) != 0 {
+            spin_loop();
+        }
+
+        // Safety: the pointer came from `Box::into_raw`, and the wait above means that no guard refers to it.
+        unsafe { Box::from_raw(old) }.value
+    }
+
+    /// Get the latest value.
+    ///
+    /// Never blocks, though it retries if a publish happens at the same time.
+    pub fn load_latest(&self) -> Guard<'_, T> {
+        loop {
+            let epoch = self.epoch.load(Ordering::SeqCst);
+            let slot = epoch & 1;
+            self.readers[slot].fetch_add(1, Ordering::SeqCst);
+            fence(Ordering::SeqCst);
+            if self.epoch.load(Ordering::SeqCst) == epoch {
+                return Guard {
+                    ptr: self,
+                    node: self.current.load(Ordering::SeqCst),
+                    slot,
+                };
+            }
+
+            // A publisher may already have checked this count, so we can't rely on it being waited for.
+            self.readers[slot].fetch_sub(1, Ordering::SeqCst);
+            spin_loop();
+        }
+    }
+}
+
+impl<'a, T> Guard<'a, T> {
+    /// The generation of this value: 0 for the initial value, then increasing by one per publish.
+    pub fn generation(&self) -> u64 {
+        // Safety: publishers don't free the node while this guard is counted.
+        unsafe { (*self.node).generation }
+    }
+}
+
+impl<'a, T> std::ops::Deref for Guard<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // Safety: publishers don't free the node while this guard is counted.
+        unsafe { &(*self.node).value }
+    }
+}
+
+impl<'a, T> Drop for Guard<'a, T> {
+    fn drop(&mut self) {
+        self.ptr.readers[self.slot].fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl<T> Drop for GenerationalPtr<T> {
+    fn drop(&mut self) {
+        // Safety: guards borrow the pointer, so there are none, and the current node is owned by us.
+        drop(unsafe { Box::from_raw(self.current.load(Ordering::Relaxed)) });
+    }
+}
+
+// Sending the pointer sends the value, which `PhantomData` handles.  Sharing it lets any thread read the value, and
+// also lets a publisher on one thread take back a value published on another, so sharing needs both bounds.
+unsafe impl<T: Send + Sync> Sync for GenerationalPtr<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::sync::{Arc, AtomicU64};
+
+    struct Snapshot {
+        a: u64,
+        b: u64,
+
+        /// One more than the highest generation which has been freed, shared by every snapshot.
+        freed: Arc<AtomicU64>,
+    }
+
+    impl Snapshot {
+        fn new(value: u64, freed: &Arc<AtomicU64>) -> Box<Snapshot> {
+            Box::new(Snapshot {
+                a: value,
+                b: value,
+                freed: freed.clone(),
+            })
+        }
+    }
+
+    impl Drop for Snapshot {
+        fn drop(&mut self) {
+            self.freed.fetch_max(self.a + 1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_generations() {
+        crate::sync::wrap_test(test_generations_inner);
+    }
+
+    fn test_generations_inner() {
+        let freed = Arc::new(AtomicU64::new(0));
+        let ptr = GenerationalPtr::new(Snapshot::new(0, &freed));
+
+        {
+            let guard = ptr.load_latest();
+            assert_eq!((guard.generation(), guard.a), (0, 0));
+        }
+
+        for i in 1..=3 {
+            let old = ptr.publish(Snapshot::new(i, &freed));
+            assert_eq!(old.a, i - 1);
+            let guard = ptr.load_latest();
+            assert_eq!((guard.generation(), guard.a), (i, i));
+        }
+
+        // Nothing was freed behind our back: only the returned boxes, and only when we dropped them.
+        assert_eq!(freed.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_publish_and_load() {
+        crate::sync::wrap_test(test_publish_and_load_inner);
+    }
+
+    fn test_publish_and_load_inner() {
+        #[cfg(loom)]
+        const PUBLISHES: u64 = 2;
+        #[cfg(not(loom))]
+        const PUBLISHES: u64 = 10000;
+
+        let freed = Arc::new(AtomicU64::new(0));
+        let ptr = Arc::new(GenerationalPtr::new(Snapshot::new(0, &freed)));
+
+        let publisher_ptr = ptr.clone();
+        let publisher_freed = freed.clone();
+        let publisher = crate::sync::spawn(move || {
+            for i in 1..=PUBLISHES {
+                let old = publisher_ptr.publish(Snapshot::new(i, &publisher_freed));
+                assert_eq!(old.a, i - 1);
+            }
+        });
+
+        let reader = crate::sync::spawn(move || {
+            let mut last = 0;
+            while last != PUBLISHES {
+                let guard = ptr.load_latest();
+                // Never torn, and a generation always means the same value.
+                assert_eq!(guard.a, guard.b);
+                assert_eq!(guard.generation(), guard.a);
+                // Never older than what we saw before.
+                assert!(guard.generation() >= last);
+                // The value we hold hasn't been freed.
+                assert!(freed.load(Ordering::SeqCst) <= guard.generation());
+                last = guard.generation();
+                drop(guard);
+                crate::sync::yield_now();
+            }
+        });
+
+        publisher.join().unwrap();
+        reader.join().unwrap();
+    }
+}
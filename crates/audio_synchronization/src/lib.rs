@@ -7,5 +7,12 @@
 //! Crossbeam's unbounded channels and queues deallocate on the receiving side, even when using operations which
 //! ostensibly don't block.
 
+pub mod deferred_drop;
+pub mod generational_atomic;
+pub mod mpsc_ring;
+/// The ring was first asked for as `mpmc_ring`, but it has one consumer, so it lives at [mpsc_ring].  This alias keeps
+/// the requested path working.
+pub use mpsc_ring as mpmc_ring;
+pub mod seqlock;
 pub mod spsc_queue;
 mod sync;
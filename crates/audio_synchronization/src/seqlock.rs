@@ -0,0 +1,250 @@
+//! A sequence lock, for sharing small plain-data values with one writer and any number of readers.
+//!
+//! The writer never blocks, and readers retry until they see a consistent value.  This is appropriate for small values
+//! which are written rarely relative to how often they are read, for example a listener position written by a control
+//! thread and read once a block by the audio thread.  Readers are not wait-free: a writer which writes in a tight loop
+//! can starve them.
+use std::mem::{size_of, MaybeUninit};
+
+use crate::sync::{fence, spin_loop, Arc, AtomicUsize, Ordering};
+
+// The implementation is the classic one:
+//
+// - The sequence counter is even when no write is in progress, and odd while one is.
+// - The writer makes the counter odd, writes, then makes it even again.
+// - Readers read the counter, read the value, then read the counter again.  If the counter was odd or changed, a write
+//   overlapped the read, so some words may be from the old value and some from the new.  Such a torn value was never
+//   written by anyone and may not even be a valid `T`, so it is thrown away and the read retried.
+// - The value itself is stored as relaxed atomic words rather than in an `UnsafeCell`.  Readers and the writer race on
+//   it by design, and that race is only defined behavior if the accesses are atomic.  Relaxed is enough: the fences
+//   around the counter are what order the words with respect to the sequence.
+// - Converting a value to words reads every byte of it as an integer, which is why values must be [NoPadding].  Reads
+//   assemble the words into a `MaybeUninit<T>`, which is only assumed initialized once the counter check passes.
+// - The single writer is guaranteed by Rust's type system: writing takes `&mut self` and the writer half doesn't impl
+//   Clone, so only one thread can be writing at a time even if the writer is shared.
+
+/// Types which contain no padding bytes, so that every byte of a value may be read as an integer.
+///
+/// # Safety
+///
+/// Implementors must have no padding, either between fields or at the end.  Structs of fields which are all the same
+/// primitive type qualify.
+pub unsafe trait NoPadding: Copy {}
+
+macro_rules! impl_no_padding {
+    ($($t: ty),*) => {
+        $(unsafe impl NoPadding for $t {})*
+    };
+}
+
+impl_no_padding!(
+    (),
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    f32,
+    f64,
+    bool,
+    char
+);
+
+unsafe impl<T: NoPadding, const N: usize> NoPadding for [T; N] {}
+
+const WORD: usize = size_of::<usize>();
+
+struct SeqLockShared {
+    sequence: AtomicUsize,
+    words: Box<[AtomicUsize]>,
+}
+
+/// The writing half of a [seqlock].
+pub struct SeqLockWriter<T: NoPadding> {
+    shared: Arc<SeqLockShared>,
+    _phantom: std::marker::PhantomData<T>,
+}
+
+/// The reading half of a [seqlock].
+///
+/// Readers may be cloned freely.
+pub struct SeqLockReader<T: NoPadding> {
+    shared: Arc<SeqLockShared>,
+    _phantom: std::marker::PhantomData<T>,
+}
+
+/// Construct a sequence lock holding `initial`.
+pub fn seqlock<T: NoPadding + Send>(initial: T) -> (SeqLockWriter<T>, SeqLockReader<T>) {
+    // One word per started `WORD` bytes.
+    let word_count = (0..size_of::<T>()).step_by(WORD).count();
+    let shared = Arc::new(SeqLockShared {
+        sequence: AtomicUsize::new(0),
+        words: (0..word_count).map(|_| AtomicUsize::new(0)).collect(),
+    });
+    store_words(&shared.words, &initial);
+
+    (
+        SeqLockWriter {
+            shared: shared.clone(),
+            _phantom: Default::default(),
+        },
+        SeqLockReader {
+            shared,
+            _phantom: Default::default(),
+        },
+    )
+}
+
+fn store_words<T: NoPadding>(words: &[AtomicUsize], value: &T) {
+    let src = (value as *const T).cast::<u8>();
+    for (i, word) in words.iter().enumerate() {
+        let start = i * WORD;
+        let len = WORD.min(size_of::<T>() - start);
+        let mut bytes = [0u8; WORD];
+        // Safety: T has no padding, so all of these bytes are initialized.
+        unsafe {
+            std::ptr::copy_nonoverlapping(src.add(start), bytes.as_mut_ptr(), len);
+        }
+        word.store(usize::from_ne_bytes(bytes), Ordering::Relaxed);
+    }
+}
+
+fn load_words<T: NoPadding>(words: &[AtomicUsize]) -> MaybeUninit<T> {
+    let mut out = MaybeUninit::<T>::uninit();
+    let dest = out.as_mut_ptr().cast::<u8>();
+    for (i, word) in words.iter().enumerate() {
+        let start = i * WORD;
+        let len = WORD.min(size_of::<T>() - start);
+        let bytes = word.load(Ordering::Relaxed).to_ne_bytes();
+        unsafe {
+            std::ptr::copy_nonoverlapping(bytes.as_ptr(), dest.add(start), len);
+        }
+    }
+    out
+}
+
+impl<T: NoPadding> SeqLockWriter<T> {
+    /// Publish a new value.
+    ///
+    /// Never blocks.
+    pub fn write(&mut self, value: T) {
+        let shared = &*self.shared;
+        let seq = shared.sequence.load(Ordering::Relaxed);
+        shared
+            .sequence
+            .store(seq.wrapping_add(1), Ordering::Relaxed);
+        // Don't let the writes to the value move above the counter becoming odd.
+        fence(Ordering::Release);
+        store_words(&shared.words, &value);
+        shared
+            .sequence
+            .store(seq.wrapping_add(2), Ordering::Release);
+    }
+}
+
+impl<T: NoPadding> SeqLockReader<T> {
+    /// Read the current value, retrying if a write is in progress.
+    pub fn read(&self) -> T {
+        let shared = &*self.shared;
+        loop {
+            let before = shared.sequence.load(Ordering::Acquire);
+            if before & 1 == 1 {
+                spin_loop();
+                continue;
+            }
+
+            let value = load_words::<T>(&shared.words);
+            // Don't let the reads of the value move below the second read of the counter.
+            fence(Ordering::Acquire);
+            let after = shared.sequence.load(Ordering::Relaxed);
+            if before == after {
+                // Safety: no write overlapped, so these are exactly the bytes of a value the writer stored.
+                return unsafe { value.assume_init() };
+            }
+
+            // The value may be torn, so discard it.
+            spin_loop();
+        }
+    }
+}
+
+impl<T: NoPadding> Clone for SeqLockReader<T> {
+    fn clone(&self) -> Self {
+        SeqLockReader {
+            shared: self.shared.clone(),
+            _phantom: Default::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Copy, Clone, Debug, Default)]
+    struct Position {
+        x: u64,
+        y: u64,
+        z: u64,
+    }
+
+    unsafe impl NoPadding for Position {}
+
+    #[test]
+    fn test_partial_words() {
+        crate::sync::wrap_test(test_partial_words_inner);
+    }
+
+    fn test_partial_words_inner() {
+        let (mut writer, reader) = seqlock([1u8, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]);
+        assert_eq!(reader.read(), [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]);
+        writer.write([11, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1]);
+        assert_eq!(reader.read(), [11, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1]);
+
+        let (mut writer, reader) = seqlock(());
+        writer.write(());
+        reader.read();
+    }
+
+    #[test]
+    fn test_no_tearing() {
+        crate::sync::wrap_test(test_no_tearing_inner);
+    }
+
+    fn test_no_tearing_inner() {
+        #[cfg(loom)]
+        const WRITES: u64 = 2;
+        #[cfg(not(loom))]
+        const WRITES: u64 = 100000;
+
+        let (mut writer, reader) = seqlock(Position::default());
+
+        let writer_thread = crate::sync::spawn(move || {
+            for i in 1..=WRITES {
+                writer.write(Position { x: i, y: i, z: i });
+            }
+        });
+
+        let reader_thread = crate::sync::spawn(move || {
+            let mut last = 0;
+            while last != WRITES {
+                let got = reader.read();
+                assert_eq!(got.x, got.y);
+                assert_eq!(got.x, got.z);
+                assert!(got.x >= last);
+                last = got.x;
+                crate::sync::yield_now();
+            }
+        });
+
+        writer_thread.join().unwrap();
+        reader_thread.join().unwrap();
+    }
+}
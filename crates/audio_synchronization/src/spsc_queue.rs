@@ -127,6 +127,9 @@ struct SpscQueue<T: Copy> {
     /// The producer's index, which is shared state because it is used by the consumer to know when an item is available.
     producer_index: AtomicU64,
 
+    /// Mirrors the consumer's `next_read`, so that the producer can know how many items are outstanding.
+    consumer_index: AtomicU64,
+
     /// the size of each chunk in this queue.
     chunk_size: NonZeroUsize,
 }
@@ -151,6 +154,7 @@ impl<T: Copy> SpscQueue<T> {
             chunk_size,
             freelist_head: AtomicPtr::new(null_mut()),
             producer_index: AtomicU64::new(0),
+            consumer_index: AtomicU64::new(0),
         }
     }
 
@@ -168,6 +172,8 @@ impl<T: Copy> SpscQueue<T> {
 
         if std::mem::size_of::<T>() == 0 {
             cstate.next_read += 1;
+            self.consumer_index
+                .store(cstate.next_read, Ordering::Release);
             return Some(NonNull::<T>::dangling().as_ptr().read());
         }
 
@@ -181,6 +187,8 @@ impl<T: Copy> SpscQueue<T> {
                     .add((cstate.next_read - chunk_start).try_into().unwrap())
                     .read();
                 cstate.next_read += 1;
+                self.consumer_index
+                    .store(cstate.next_read, Ordering::Release);
                 return Some(out);
             }
 
@@ -243,6 +251,15 @@ impl<T: Copy> SpscQueue<T> {
         self.producer_index.fetch_add(1, Ordering::Release);
     }
 
+    /// How many items have been enqueued but not yet dequeued?
+    ///
+    /// Only exact from the producer's side: the consumer may dequeue concurrently, so this is an upper bound.
+    fn outstanding(&self) -> u64 {
+        let consumer_index = self.consumer_index.load(Ordering::Acquire);
+        let producer_index = self.producer_index.load(Ordering::Relaxed);
+        producer_index - consumer_index
+    }
+
     unsafe fn find_or_alloc_chunk(&self, first_index: u64) -> NonNull<ChunkHeader<T>> {
         let mut freelist_head = self.freelist_head.load(Ordering::Relaxed);
         while !freelist_head.is_null() {
@@ -363,6 +380,23 @@ impl<T: Copy> SpscSender<T> {
             self.queue.inner.as_ref().enqueue(val);
         }
     }
+
+    /// Enqueue an item into this queue, unless there are already `max_outstanding` items which the receiver has yet to
+    /// consume.
+    ///
+    /// On failure, the value is handed back.  This lets producers cap the memory used by the queue: if
+    /// `max_outstanding` is at most the chunk size, the queue stops allocating once it reaches a steady state.
+    pub fn send_bounded(&mut self, val: T, max_outstanding: usize) -> Result<(), T> {
+        let queue = unsafe { self.queue.inner.as_ref() };
+        if queue.outstanding() >= max_outstanding as u64 {
+            return Err(val);
+        }
+
+        unsafe {
+            queue.enqueue(val);
+        }
+        Ok(())
+    }
 }
 
 impl<T: Copy> SpscReceiver<T> {
@@ -415,4 +449,33 @@ mod tests {
         let got = receiver_thread.join().unwrap();
         assert_eq!(got, vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
     }
+
+    #[test]
+    fn test_send_bounded() {
+        crate::sync::wrap_test(test_send_bounded_inner);
+    }
+
+    fn test_send_bounded_inner() {
+        let (mut sender, mut receiver) = spsc_queue::<Counter>(NonZeroUsize::new(3).unwrap());
+
+        for i in 0..3 {
+            sender.send_bounded(Counter(i), 3).unwrap();
+        }
+        assert_eq!(sender.send_bounded(Counter(3), 3), Err(Counter(3)));
+
+        assert_eq!(receiver.recv(), Some(Counter(0)));
+        sender.send_bounded(Counter(3), 3).unwrap();
+        assert_eq!(sender.send_bounded(Counter(4), 3), Err(Counter(4)));
+
+        let mut got = vec![];
+        while let Some(c) = receiver.recv() {
+            got.push(c.0);
+        }
+        assert_eq!(got, vec![1, 2, 3]);
+
+        for i in 4..7 {
+            sender.send_bounded(Counter(i), 3).unwrap();
+        }
+        assert_eq!(sender.send_bounded(Counter(7), 3), Err(Counter(7)));
+    }
 }
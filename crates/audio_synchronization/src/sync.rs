@@ -1,5 +1,6 @@
 #[cfg(not(loom))]
 mod not_loom {
+    pub use std::hint::spin_loop;
     pub use std::sync::atomic::*;
     pub use std::sync::*;
     pub use std::thread::spawn;
@@ -16,6 +17,7 @@ pub(crate) use not_loom::*;
 
 #[cfg(loom)]
 mod with_loom {
+    pub use loom::hint::spin_loop;
     pub use loom::sync::atomic::*;
     pub use loom::sync::*;
     pub use loom::thread::spawn;
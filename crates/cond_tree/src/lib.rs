@@ -86,6 +86,9 @@ pub enum Cond<F, S> {
 /// - [MaybeInt]: a divergence which becomes a constant if a given value matches a compile-time-provided value.  Useful
 ///       for example when working with strided array accesses where the stride is often one.
 ///
+/// Fieldless enums may also `#[derive(Divergence)]`, which treats one variant as the fast path and the rest as the slow
+/// path.  See the derive's documentation for details.
+///
 ///
 /// This crate also provides an implementation for tuples of conds, in order to allow building more complex trees where
 /// some conditions are correlated.  This is useful because the tree is O(n^2), so if there are some subset of those
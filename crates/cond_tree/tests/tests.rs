@@ -180,3 +180,77 @@ fn nested_diverging() {
 
     assert_eq!(got, vec![(4, 7), (4, 8), (5, 7), (5, 8)]);
 }
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Divergence)]
+enum TwoVariants {
+    Common,
+    Rare,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Divergence)]
+enum ThreeVariants {
+    A,
+    #[divergence(fast)]
+    B,
+    C,
+}
+
+#[test]
+#[diverge_fn]
+fn derived_divergence() {
+    assert!(TwoVariants::Common.evaluate_divergence().is_fast());
+    assert!(TwoVariants::Rare.evaluate_divergence().is_slow());
+
+    let mut got = vec![];
+
+    for e in [TwoVariants::Common, TwoVariants::Rare] {
+        #[diverge(e)]
+        {
+            got.push(e.get());
+        }
+    }
+
+    assert_eq!(got, vec![TwoVariants::Common, TwoVariants::Rare]);
+}
+
+#[test]
+#[diverge_fn]
+fn derived_divergence_marked_fast() {
+    assert!(ThreeVariants::B.evaluate_divergence().is_fast());
+    assert!(ThreeVariants::A.evaluate_divergence().is_slow());
+    assert!(ThreeVariants::C.evaluate_divergence().is_slow());
+
+    for e in [ThreeVariants::A, ThreeVariants::B, ThreeVariants::C] {
+        let got;
+
+        #[diverge(e)]
+        {
+            got = e.get();
+        }
+
+        assert_eq!(got, e);
+    }
+
+    // The fast side is usable in constant contexts.
+    const FAST: ThreeVariants = ThreeVariantsFast.get();
+    assert_eq!(FAST, ThreeVariants::B);
+}
+
+#[test]
+fn derived_divergence_tuple_collapsing() {
+    match (TwoVariants::Common, ThreeVariants::B).evaluate_divergence() {
+        Cond::Fast((a, b)) => {
+            assert_eq!(a.get(), TwoVariants::Common);
+            assert_eq!(b.get(), ThreeVariants::B);
+        }
+        Cond::Slow(_) => panic!("Expected the fast path"),
+    }
+
+    match (TwoVariants::Common, ThreeVariants::C).evaluate_divergence() {
+        Cond::Slow((a, b)) => {
+            assert_eq!(a.get(), TwoVariants::Common);
+            assert_eq!(b.get(), ThreeVariants::C);
+        }
+        Cond::Fast(_) => panic!("Expected the slow path"),
+    }
+}
@@ -350,6 +350,115 @@ pub fn diverge_fn(_attribute: TokenStream, input: TokenStream) -> TokenStream {
     quote::quote!(#output).into()
 }
 
+/// Derive `Divergence` for a fieldless enum.
+///
+/// One variant is the fast path: the first variant by default, or the variant marked `#[divergence(fast)]`.  All
+/// other variants are the slow path.  For an enum `Mode`, this generates two types with the same visibility as the
+/// enum:
+///
+/// - `ModeFast`, a zero-sized type whose `get()` is a `const fn` returning the fast variant.  In the fast branch of a
+///   divergence, matching on `get()` therefore folds away.
+/// - `ModeSlow`, which wraps the runtime value and also has a `get()`.
+///
+/// `ModeSlow: From<ModeFast>` is also generated, so the enum may be used in tuples of divergences.
+///
+/// The enum must be fieldless, must not be generic, and must be `Copy`.
+#[proc_macro_derive(Divergence, attributes(divergence))]
+#[proc_macro_error::proc_macro_error]
+pub fn derive_divergence(input: TokenStream) -> TokenStream {
+    let input: syn::DeriveInput = syn::parse_macro_input!(input);
+
+    let data = match &input.data {
+        syn::Data::Enum(e) => e,
+        _ => abort!(input.ident, "Divergence may only be derived for enums"),
+    };
+
+    if !input.generics.params.is_empty() {
+        abort!(
+            input.generics,
+            "Divergence may not be derived for generic enums"
+        );
+    }
+
+    if data.variants.is_empty() {
+        abort!(
+            input.ident,
+            "Divergence may not be derived for enums without variants"
+        );
+    }
+
+    let mut fast = None;
+    for v in data.variants.iter() {
+        if !matches!(v.fields, syn::Fields::Unit) {
+            abort!(v, "Divergence may only be derived for fieldless enums");
+        }
+
+        for a in v.attrs.iter().filter(|a| a.path.is_ident("divergence")) {
+            let arg: syn::Ident = a.parse_args().unwrap_or_abort();
+            if arg != "fast" {
+                abort!(arg, "Expected #[divergence(fast)]");
+            }
+
+            if fast.is_some() {
+                abort!(a, "Only one variant may be marked #[divergence(fast)]");
+            }
+            fast = Some(&v.ident);
+        }
+    }
+    let fast = fast.unwrap_or(&data.variants[0].ident);
+
+    let vis = &input.vis;
+    let name = &input.ident;
+    let fast_name = quote::format_ident!("{}Fast", name);
+    let slow_name = quote::format_ident!("{}Slow", name);
+    let fast_doc =
+        format!("The fast side of the divergence for [{name}], always [{name}::{fast}].");
+    let slow_doc = format!("The slow side of the divergence for [{name}].");
+
+    quote!(
+        #[doc = #fast_doc]
+        #[derive(Copy, Clone, Debug, Default)]
+        #vis struct #fast_name;
+
+        #[doc = #slow_doc]
+        #[derive(Copy, Clone)]
+        #vis struct #slow_name(#name);
+
+        impl #fast_name {
+            #[inline(always)]
+            pub const fn get(&self) -> #name {
+                #name::#fast
+            }
+        }
+
+        impl #slow_name {
+            #[inline(always)]
+            pub const fn get(&self) -> #name {
+                self.0
+            }
+        }
+
+        impl From<#fast_name> for #slow_name {
+            fn from(_input: #fast_name) -> #slow_name {
+                #slow_name(#name::#fast)
+            }
+        }
+
+        impl cond_tree::Divergence for #name {
+            type Fast = #fast_name;
+            type Slow = #slow_name;
+
+            fn evaluate_divergence(self) -> cond_tree::Cond<Self::Fast, Self::Slow> {
+                match self {
+                    #name::#fast => cond_tree::Cond::Fast(#fast_name),
+                    x => cond_tree::Cond::Slow(#slow_name(x)),
+                }
+            }
+        }
+    )
+    .into()
+}
+
 /// An internal implementation detail. Punches out the traits for tuples.
 ///
 /// impl_trait_for_tuples turns out not to be quite flexible enough to let us build an intermediate tuple, then unfold
@@ -0,0 +1,33 @@
+use itertools::Itertools;
+use std::io::Write;
+
+/// How many segments the table has over one period.  Must be a power of 2.
+const SIN_TABLE_SEGMENTS: usize = 1024;
+
+pub fn gen_sin_table() {
+    // One extra entry so that interpolation never needs to wrap.
+    let values = (0..=SIN_TABLE_SEGMENTS)
+        .map(|i| {
+            (2.0 * std::f64::consts::PI * (i % SIN_TABLE_SEGMENTS) as f64
+                / SIN_TABLE_SEGMENTS as f64)
+                .sin()
+        })
+        .map(|x| format!("{x:?}"))
+        .join(",\n");
+    let len = SIN_TABLE_SEGMENTS + 1;
+
+    let out = format!(
+        r#"
+/// One period of `sin`, in {SIN_TABLE_SEGMENTS} segments, with the first value repeated at the end.
+#[allow(clippy::approx_constant)]
+const SIN_TABLE: [f64; {len}] = [
+{values}
+];
+"#
+    );
+
+    let out_dir = std::env::var("OUT_DIR").unwrap();
+    let final_path = format!("{out_dir}/sin_table.rs");
+    let mut file = std::fs::File::create(&final_path).unwrap();
+    file.write_all(out.as_bytes()).unwrap();
+}
@@ -1,8 +1,10 @@
 mod gen_hrtf;
 mod gen_primes;
+mod gen_sin_table;
 
 fn main() {
     println!("cargo:rerun-if-changed=src/datasets/bin_protos");
     gen_primes::gen_primes();
+    gen_sin_table::gen_sin_table();
     gen_hrtf::gen_hrtf();
 }
@@ -25,7 +25,10 @@ pub struct MonoBiquadFilter {
 }
 
 /// A definition for a biquad filter.
+///
+/// With the `serde` feature, definitions serialize as their normalized coefficients.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BiquadFilterDef {
     gain: f64,
     b1: f64,
@@ -48,11 +51,32 @@ impl MonoBiquadFilter {
         //
         // Put the gain in first.
         let with_gain = input_sample * self.def.gain;
-        let recursive = with_gain + self.def.a1 * self.history[0] + self.def.a2 * self.history[1];
+        let recursive = with_gain - self.def.a1 * self.history[0] - self.def.a2 * self.history[1];
         let out = recursive + self.def.b1 * self.history[0] + self.def.b2 * self.history[1];
-        self.history.swap(0, 1);
+        self.history = [recursive, self.history[0]];
         out
     }
+
+    /// Clear the history of this filter, as if it was just created.
+    pub fn reset(&mut self) {
+        self.history = [0.0; 2];
+    }
+
+    /// Set the history of this filter to what it would be after being fed `dc_value` forever.
+    ///
+    /// This avoids the startup transient when inserting a filter into a signal which is already running and roughly
+    /// constant: the next output is immediately the filter's steady-state response to `dc_value`.  Filters with a pole
+    /// exactly at DC have no steady state, and are instead reset.
+    pub fn prime(&mut self, dc_value: f64) {
+        let denom = 1.0 + self.def.a1 + self.def.a2;
+        if denom == 0.0 {
+            self.reset();
+            return;
+        }
+
+        let steady = dc_value * self.def.gain / denom;
+        self.history = [steady; 2];
+    }
 }
 
 // Some helpers which compute common variables from the Audio EQ cookbook.
@@ -86,6 +110,7 @@ fn bq_alpha_s(omega0: f64, s: f64, a: f64) -> f64 {
 ///
 /// Note that the unit for `Bw` is octaves.  To get a bandwidth for a specific frequency and range, use [AudioEqAlpha::bw_from_hz].
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AudioEqAlpha {
     Q(f64),
     Bw(f64),
@@ -272,6 +297,128 @@ impl BiquadFilterDef {
     }
 }
 
+/// A chain of biquad filters, run one after the other.
+#[derive(Debug, Clone)]
+pub struct BiquadCascade {
+    filters: Vec<MonoBiquadFilter>,
+}
+
+impl BiquadCascade {
+    pub fn new(defs: impl IntoIterator<Item = BiquadFilterDef>) -> BiquadCascade {
+        BiquadCascade {
+            filters: defs.into_iter().map(MonoBiquadFilter::new).collect(),
+        }
+    }
+
+    /// Tick all filters in the cascade by 1 sample.
+    pub fn tick(&mut self, input_sample: f64) -> f64 {
+        self.filters
+            .iter_mut()
+            .fold(input_sample, |acc, f| f.tick(acc))
+    }
+
+    /// Clear the history of all filters in the cascade.
+    pub fn reset(&mut self) {
+        self.filters.iter_mut().for_each(|f| f.reset());
+    }
+
+    /// Get the frequency response of the whole cascade, given a frequency in hz.
+    pub fn frequency_response(&self, frequency: f64) -> Complex64 {
+        self.filters
+            .iter()
+            .map(|f| f.def.frequency_response(frequency))
+            .product()
+    }
+}
+
+/// The kinds of band in an [EqPreset], named after the Audio EQ Cookbook filters they build.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum EqBandKind {
+    Lowpass,
+    Highpass,
+    BandpassPeak0,
+    Notch,
+    Allpass,
+    Peaking,
+    Lowshelf,
+    Highshelf,
+}
+
+/// One band of an [EqPreset].
+///
+/// `gain_db` is used only by peaking and shelf bands, which are also the only kinds that accept [AudioEqAlpha::S].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EqBand {
+    pub kind: EqBandKind,
+    pub frequency: f64,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub gain_db: f64,
+    pub alpha: AudioEqAlpha,
+}
+
+/// Reasons an [EqBand] cannot be turned into a filter.
+#[derive(Debug, thiserror::Error)]
+pub enum EqError {
+    #[error("{kind:?} bands do not accept S as a parameter; only peaking and shelf bands do")]
+    SNotSupported { kind: EqBandKind },
+}
+
+impl EqBandKind {
+    fn accepts_s(&self) -> bool {
+        matches!(
+            self,
+            EqBandKind::Peaking | EqBandKind::Lowshelf | EqBandKind::Highshelf
+        )
+    }
+}
+
+impl EqBand {
+    /// Build the filter for this band.
+    ///
+    /// Bands usually come from configuration files, so unsupported parameter combinations are errors rather than the
+    /// panics the [BiquadFilterDef] constructors give.
+    pub fn filter_def(&self) -> Result<BiquadFilterDef, EqError> {
+        if matches!(self.alpha, AudioEqAlpha::S(_)) && !self.kind.accepts_s() {
+            return Err(EqError::SNotSupported { kind: self.kind });
+        }
+
+        let (f, g, a) = (self.frequency, self.gain_db, self.alpha);
+        Ok(match self.kind {
+            EqBandKind::Lowpass => BiquadFilterDef::audio_eq_lowpass(f, a),
+            EqBandKind::Highpass => BiquadFilterDef::audio_eq_highpass(f, a),
+            EqBandKind::BandpassPeak0 => BiquadFilterDef::audio_eq_bandpass_peak_0(f, a),
+            EqBandKind::Notch => BiquadFilterDef::audio_eq_notch(f, a),
+            EqBandKind::Allpass => BiquadFilterDef::audio_eq_allpass(f, a),
+            EqBandKind::Peaking => BiquadFilterDef::audio_eq_peaking(f, g, a),
+            EqBandKind::Lowshelf => BiquadFilterDef::audio_eq_lowshelf(f, g, a),
+            EqBandKind::Highshelf => BiquadFilterDef::audio_eq_highshelf(f, g, a),
+        })
+    }
+}
+
+/// A parametric EQ, as a list of bands which are applied in order.
+///
+/// This is the form intended for storing in configuration files: with the `serde` feature, presets serialize as their
+/// band parameters rather than as coefficients.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EqPreset {
+    pub bands: Vec<EqBand>,
+}
+
+impl EqPreset {
+    pub fn filter_defs(&self) -> Result<Vec<BiquadFilterDef>, EqError> {
+        self.bands.iter().map(|b| b.filter_def()).collect()
+    }
+
+    pub fn build_cascade(&self) -> Result<BiquadCascade, EqError> {
+        Ok(BiquadCascade::new(self.filter_defs()?))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -463,4 +610,203 @@ mod tests {
             0.003,
         );
     }
+
+    #[test]
+    fn test_tick_steady_state() {
+        // After a long run of DC, a lowpass passes it and a highpass blocks it.
+        let mut lowpass = MonoBiquadFilter::new(BiquadFilterDef::audio_eq_lowpass(
+            1000.0,
+            AudioEqAlpha::Q(DEFAULT_Q),
+        ));
+        let mut highpass = MonoBiquadFilter::new(BiquadFilterDef::audio_eq_highpass(
+            1000.0,
+            AudioEqAlpha::Q(DEFAULT_Q),
+        ));
+
+        let (mut low, mut high) = (0.0, 0.0);
+        for _ in 0..10000 {
+            low = lowpass.tick(0.5);
+            high = highpass.tick(0.5);
+        }
+
+        close_floats64(low, 0.5, 0.0001);
+        close_floats64(high, 0.0, 0.0001);
+    }
+
+    #[test]
+    fn test_impulse_response() {
+        fn impulse_response(def: BiquadFilterDef, len: usize) -> Vec<f64> {
+            let mut filt = MonoBiquadFilter::new(def);
+            (0..len)
+                .map(|i| filt.tick(if i == 0 { 1.0 } else { 0.0 }))
+                .collect()
+        }
+
+        // Worked by hand from `y[n] = b0 x[n] + b1 x[n - 1] + b2 x[n - 2] - a1 y[n - 1] - a2 y[n - 2]`.
+        let first_order = impulse_response(
+            BiquadFilterDef::new_raw([2.0, 1.0, 0.0], [1.0, -0.5, 0.0]),
+            6,
+        );
+        assert_eq!(first_order, [2.0, 2.0, 1.0, 0.5, 0.25, 0.125]);
+        let second_order = impulse_response(
+            BiquadFilterDef::new_raw([1.0, 0.0, 0.0], [1.0, -1.0, 0.5]),
+            8,
+        );
+        assert_eq!(
+            second_order,
+            [1.0, 1.0, 0.5, 0.0, -0.25, -0.25, -0.125, 0.0]
+        );
+
+        // And a cookbook filter against a direct form 1 implementation of the same equation.
+        let omega0 = bq_omega0(1000.0);
+        let alpha = bq_alpha_q(omega0, DEFAULT_Q);
+        let cos = omega0.cos();
+        let b = [(1.0 - cos) / 2.0, 1.0 - cos, (1.0 - cos) / 2.0];
+        let a = [1.0 + alpha, -2.0 * cos, 1.0 - alpha];
+        let got = impulse_response(BiquadFilterDef::new_raw(b, a), 64);
+
+        let mut x = [0.0f64; 3];
+        let mut y = [0.0f64; 3];
+        for (i, got) in got.into_iter().enumerate() {
+            x = [if i == 0 { 1.0 } else { 0.0 }, x[0], x[1]];
+            y = [0.0, y[0], y[1]];
+            y[0] = (b[0] * x[0] + b[1] * x[1] + b[2] * x[2] - a[1] * y[1] - a[2] * y[2]) / a[0];
+            close_floats64(got, y[0], 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_prime_and_reset() {
+        let mut filt = MonoBiquadFilter::new(BiquadFilterDef::audio_eq_lowpass(
+            1000.0,
+            AudioEqAlpha::Q(DEFAULT_Q),
+        ));
+
+        // Unprimed, the filter ramps up from silence.
+        let first = filt.tick(0.5);
+        assert!(first < 0.1, "{}", first);
+
+        filt.prime(0.5);
+        for _ in 0..100 {
+            close_floats64(filt.tick(0.5), 0.5, 0.000001);
+        }
+
+        filt.reset();
+        assert_eq!(filt.tick(0.5), first);
+    }
+
+    fn test_preset() -> EqPreset {
+        EqPreset {
+            bands: vec![
+                EqBand {
+                    kind: EqBandKind::Highpass,
+                    frequency: 80.0,
+                    gain_db: 0.0,
+                    alpha: AudioEqAlpha::Q(DEFAULT_Q),
+                },
+                EqBand {
+                    kind: EqBandKind::Peaking,
+                    frequency: 2500.0,
+                    gain_db: -4.0,
+                    alpha: AudioEqAlpha::Bw(1.0),
+                },
+                EqBand {
+                    kind: EqBandKind::Highshelf,
+                    frequency: 8000.0,
+                    gain_db: 3.0,
+                    alpha: AudioEqAlpha::S(1.0),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_cascade() {
+        let preset = test_preset();
+        let defs = preset.filter_defs().unwrap();
+        let mut cascade = preset.build_cascade().unwrap();
+        let mut filters = defs
+            .iter()
+            .cloned()
+            .map(MonoBiquadFilter::new)
+            .collect::<Vec<_>>();
+
+        for freq in [50.0, 1000.0, 2500.0, 12000.0] {
+            let expected = defs
+                .iter()
+                .map(|d| d.frequency_response(freq))
+                .product::<Complex64>();
+            let got = cascade.frequency_response(freq);
+            close_floats64(got.re, expected.re, 1e-12);
+            close_floats64(got.im, expected.im, 1e-12);
+        }
+
+        for i in 0..100 {
+            let input = (i as f64 * 0.3).sin();
+            let expected = filters.iter_mut().fold(input, |acc, f| f.tick(acc));
+            close_floats64(cascade.tick(input), expected, 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_s_only_for_peaking_and_shelves() {
+        let mut band = EqBand {
+            kind: EqBandKind::Lowpass,
+            frequency: 1000.0,
+            gain_db: 0.0,
+            alpha: AudioEqAlpha::S(1.0),
+        };
+        assert!(matches!(
+            band.filter_def(),
+            Err(EqError::SNotSupported {
+                kind: EqBandKind::Lowpass
+            })
+        ));
+
+        band.kind = EqBandKind::Lowshelf;
+        assert!(band.filter_def().is_ok());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_preset_serde_rejects_s_for_notch() {
+        let json = r#"{"bands": [{"kind": "notch", "frequency": 1000.0, "alpha": {"S": 1.0}}]}"#;
+        let preset: EqPreset = serde_json::from_str(json).unwrap();
+        assert!(matches!(
+            preset.build_cascade(),
+            Err(EqError::SNotSupported {
+                kind: EqBandKind::Notch
+            })
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_preset_serde() {
+        let preset = test_preset();
+        let json = serde_json::to_string(&preset).unwrap();
+        assert!(json.contains("\"highshelf\""), "{}", json);
+        let round_tripped: EqPreset = serde_json::from_str(&json).unwrap();
+
+        let before = preset.build_cascade().unwrap();
+        let after = round_tripped.build_cascade().unwrap();
+        for freq in [20.0, 80.0, 500.0, 2500.0, 8000.0, 20000.0] {
+            let (b, a) = (
+                before.frequency_response(freq),
+                after.frequency_response(freq),
+            );
+            close_floats64(a.re, b.re, 1e-12);
+            close_floats64(a.im, b.im, 1e-12);
+        }
+
+        // Definitions themselves also round trip.
+        let def = BiquadFilterDef::audio_eq_notch(1000.0, AudioEqAlpha::Q(2.0));
+        let def2: BiquadFilterDef =
+            serde_json::from_str(&serde_json::to_string(&def).unwrap()).unwrap();
+        close_floats64(
+            def.frequency_response(1200.0).norm(),
+            def2.frequency_response(1200.0).norm(),
+            1e-12,
+        );
+    }
 }
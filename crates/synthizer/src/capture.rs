@@ -0,0 +1,447 @@
+//! Capturing audio to disk.
+//!
+//! This is primarily for debugging and for baking assets.  Files are written as WAV, with either 16-bit integer or
+//! 32-bit float samples.  Float files and files with more than 2 channels use `WAVE_FORMAT_EXTENSIBLE` headers.
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::channel_format::ChannelFormat;
+
+/// The format of the samples in a WAV file.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum WavSampleFormat {
+    /// 16-bit signed integers.  Samples outside `-1.0..=1.0` are clipped.
+    I16,
+
+    /// 32-bit floats, written as-is.
+    F32,
+}
+
+impl WavSampleFormat {
+    fn bytes_per_sample(&self) -> u16 {
+        match self {
+            WavSampleFormat::I16 => 2,
+            WavSampleFormat::F32 => 4,
+        }
+    }
+
+    fn format_tag(&self) -> u16 {
+        match self {
+            WavSampleFormat::I16 => 1,
+            WavSampleFormat::F32 => 3,
+        }
+    }
+}
+
+/// The format tag saying that the real format is in the extension of the fmt chunk.
+const WAVE_FORMAT_EXTENSIBLE: u16 = 0xfffe;
+
+/// The bytes of the extensible SubFormat GUID after the first two, which are the format tag.
+const SUBFORMAT_GUID_TAIL: [u8; 14] = [
+    0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xaa, 0x00, 0x38, 0x9b, 0x71,
+];
+
+fn invalid_input(message: &'static str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidInput, message)
+}
+
+fn too_large() -> std::io::Error {
+    invalid_input("WAV files may not be larger than 4GB")
+}
+
+/// Writes interleaved audio to a WAV file.
+///
+/// The sizes in the header aren't known until the end, so they are filled in by [WavWriter::finish].  If the writer is
+/// dropped without calling `finish`, the header is finalized anyway but any error is lost.
+///
+/// If a write fails, part of it may already be in the file.  The header counts the samples which were accepted into the
+/// writer's buffer before the failure, which need not match what reached the disk, so the writer refuses any further
+/// writes and [WavWriter::finish] reports the earlier failure.
+pub struct WavWriter {
+    file: BufWriter<File>,
+    channels: usize,
+    sample_format: WavSampleFormat,
+    data_bytes: u64,
+
+    /// Where the 32-bit frame count of the fact chunk is, if there is one.
+    fact_offset: Option<u64>,
+
+    /// Where the 32-bit size of the data chunk is.
+    data_size_offset: u64,
+
+    /// The kind of the error, if a write failed partway through.
+    poisoned: Option<std::io::ErrorKind>,
+
+    finished: bool,
+}
+
+impl WavWriter {
+    /// Create a file at `path`, overwriting any file which is already there.
+    ///
+    /// Fails with [std::io::ErrorKind::InvalidInput] if the format has more channels than are supported, or too many
+    /// for a WAV header.
+    pub fn new(
+        path: impl AsRef<Path>,
+        format: &ChannelFormat,
+        sample_format: WavSampleFormat,
+        sample_rate: u32,
+    ) -> std::io::Result<WavWriter> {
+        format
+            .validate()
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+        let channels = format.get_channel_count().get();
+        let channels_u16 = u16::try_from(channels)
+            .map_err(|_| invalid_input("Too many channels for a WAV file"))?;
+        let block_align = channels_u16
+            .checked_mul(sample_format.bytes_per_sample())
+            .ok_or_else(|| invalid_input("Too many channels for a WAV file"))?;
+        let byte_rate = sample_rate
+            .checked_mul(block_align as u32)
+            .ok_or_else(|| invalid_input("The byte rate is too large for a WAV file"))?;
+        let bits = sample_format.bytes_per_sample() * 8;
+        let is_float = sample_format == WavSampleFormat::F32;
+        // Plain PCM headers are only unambiguous for mono and stereo integer files.
+        let extensible = is_float || channels > 2;
+
+        let mut file = BufWriter::new(File::create(path)?);
+
+        // Sizes are written as 0 for now and patched when finishing.
+        file.write_all(b"RIFF")?;
+        file.write_all(&0u32.to_le_bytes())?;
+        file.write_all(b"WAVE")?;
+
+        let fmt_size: u32 = if extensible { 40 } else { 16 };
+        file.write_all(b"fmt ")?;
+        file.write_all(&fmt_size.to_le_bytes())?;
+        let tag = if extensible {
+            WAVE_FORMAT_EXTENSIBLE
+        } else {
+            sample_format.format_tag()
+        };
+        file.write_all(&tag.to_le_bytes())?;
+        file.write_all(&channels_u16.to_le_bytes())?;
+        file.write_all(&sample_rate.to_le_bytes())?;
+        file.write_all(&byte_rate.to_le_bytes())?;
+        file.write_all(&block_align.to_le_bytes())?;
+        file.write_all(&bits.to_le_bytes())?;
+
+        if extensible {
+            // The speaker mask only says something for the formats we know the layout of; raw channels are left
+            // unassigned.
+            let channel_mask: u32 = match format {
+                ChannelFormat::Mono => 0x4,
+                ChannelFormat::Stereo => 0x3,
+                ChannelFormat::Raw { .. } => 0,
+            };
+            file.write_all(&22u16.to_le_bytes())?;
+            file.write_all(&bits.to_le_bytes())?;
+            file.write_all(&channel_mask.to_le_bytes())?;
+            file.write_all(&sample_format.format_tag().to_le_bytes())?;
+            file.write_all(&SUBFORMAT_GUID_TAIL)?;
+        }
+
+        // Offsets are worked out from the layout rather than asked of the file, which would flush it.  The header is
+        // the RIFF chunk header and WAVE, then the fmt chunk with its own header.
+        let mut pos = 12 + 8 + fmt_size as u64;
+
+        let mut fact_offset = None;
+        if is_float {
+            file.write_all(b"fact")?;
+            file.write_all(&4u32.to_le_bytes())?;
+            fact_offset = Some(pos + 8);
+            file.write_all(&0u32.to_le_bytes())?;
+            pos += 12;
+        }
+
+        file.write_all(b"data")?;
+        let data_size_offset = pos + 4;
+        file.write_all(&0u32.to_le_bytes())?;
+
+        Ok(WavWriter {
+            file,
+            channels,
+            sample_format,
+            data_bytes: 0,
+            fact_offset,
+            data_size_offset,
+            poisoned: None,
+            finished: false,
+        })
+    }
+
+    /// Write some interleaved frames.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length of `interleaved` is not a multiple of the channel count.
+    pub fn write(&mut self, interleaved: &[f32]) -> std::io::Result<()> {
+        assert_eq!(interleaved.len() % self.channels, 0, "Got a partial frame");
+
+        if let Some(kind) = self.poisoned {
+            return Err(std::io::Error::new(
+                kind,
+                "A previous write to this WAV file failed",
+            ));
+        }
+
+        let bytes_per_sample = self.sample_format.bytes_per_sample() as u64;
+        let new_bytes = interleaved.len() as u64 * bytes_per_sample;
+        // The data chunk size is 32 bits, and the RIFF size also has to fit the header.
+        if self.data_bytes + new_bytes > u32::MAX as u64 - self.data_size_offset {
+            return Err(too_large());
+        }
+
+        for s in interleaved.iter().copied() {
+            let res = match self.sample_format {
+                WavSampleFormat::I16 => {
+                    let i = (s.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16;
+                    self.file.write_all(&i.to_le_bytes())
+                }
+                WavSampleFormat::F32 => self.file.write_all(&s.to_le_bytes()),
+            };
+            if let Err(e) = res {
+                self.poisoned = Some(e.kind());
+                return Err(e);
+            }
+            self.data_bytes += bytes_per_sample;
+        }
+
+        Ok(())
+    }
+
+    fn finalize(&mut self) -> std::io::Result<()> {
+        if self.finished {
+            return Ok(());
+        }
+        self.finished = true;
+
+        // `write` keeps the data small enough that both sizes fit, but do the math in u64 so that it can't wrap.
+        let data_bytes = u32::try_from(self.data_bytes).map_err(|_| too_large())?;
+        let riff_size = u32::try_from(self.data_size_offset + 4 + self.data_bytes - 8)
+            .map_err(|_| too_large())?;
+        let frames =
+            self.data_bytes / (self.channels as u64 * self.sample_format.bytes_per_sample() as u64);
+
+        self.file.seek(SeekFrom::Start(4))?;
+        self.file.write_all(&riff_size.to_le_bytes())?;
+        if let Some(o) = self.fact_offset {
+            self.file.seek(SeekFrom::Start(o))?;
+            self.file.write_all(&(frames as u32).to_le_bytes())?;
+        }
+        self.file.seek(SeekFrom::Start(self.data_size_offset))?;
+        self.file.write_all(&data_bytes.to_le_bytes())?;
+        self.file.seek(SeekFrom::End(0))?;
+        self.file.flush()
+    }
+
+    /// Write the final header and flush the file.
+    ///
+    /// If an earlier write failed, the header is still written but an error is returned.
+    pub fn finish(mut self) -> std::io::Result<()> {
+        self.finalize()?;
+        if let Some(kind) = self.poisoned {
+            return Err(std::io::Error::new(
+                kind,
+                "A write to this WAV file failed, so it is incomplete",
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for WavWriter {
+    fn drop(&mut self) {
+        let _ = self.finalize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::close_floats::*;
+
+    fn u16_at(bytes: &[u8], offset: usize) -> u16 {
+        u16::from_le_bytes(bytes[offset..offset + 2].try_into().unwrap())
+    }
+
+    fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+    }
+
+    /// A minimal reader which understands what [WavWriter] writes, returning the channel count, sample rate, and samples.
+    fn read_wav(bytes: &[u8]) -> (u16, u32, Vec<f32>) {
+        assert_eq!(&bytes[0..4], b"RIFF");
+        assert_eq!(u32_at(bytes, 4) as usize, bytes.len() - 8);
+        assert_eq!(&bytes[8..12], b"WAVE");
+
+        let mut pos = 12;
+        let mut fmt = None;
+        let mut fact = None;
+        loop {
+            let id = &bytes[pos..pos + 4];
+            let size = u32_at(bytes, pos + 4) as usize;
+            let body = &bytes[pos + 8..pos + 8 + size];
+            match id {
+                b"fmt " => fmt = Some(body),
+                b"fact" => fact = Some(u32_at(body, 0)),
+                b"data" => {
+                    let fmt = fmt.unwrap();
+                    let mut tag = u16_at(fmt, 0);
+                    if tag == WAVE_FORMAT_EXTENSIBLE {
+                        assert_eq!(fmt.len(), 40);
+                        assert_eq!(u16_at(fmt, 16), 22);
+                        assert_eq!(u16_at(fmt, 18), u16_at(fmt, 14));
+                        assert_eq!(fmt[26..40], SUBFORMAT_GUID_TAIL);
+                        tag = u16_at(fmt, 24);
+                    } else {
+                        assert_eq!(fmt.len(), 16);
+                    }
+                    let channels = u16_at(fmt, 2);
+                    let sr = u32_at(fmt, 4);
+                    let samples: Vec<f32> = match tag {
+                        1 => body
+                            .chunks(2)
+                            .map(|c| i16::from_le_bytes([c[0], c[1]]) as f32 / i16::MAX as f32)
+                            .collect(),
+                        3 => {
+                            let s: Vec<f32> = body
+                                .chunks(4)
+                                .map(|c| f32::from_le_bytes(c.try_into().unwrap()))
+                                .collect();
+                            assert_eq!(fact.unwrap() as usize, s.len() / channels as usize);
+                            s
+                        }
+                        _ => panic!("Unknown format tag {}", tag),
+                    };
+                    assert_eq!(pos + 8 + size, bytes.len());
+                    return (channels, sr, samples);
+                }
+                _ => panic!("Unexpected chunk"),
+            }
+            pos += 8 + size;
+        }
+    }
+
+    fn check_format(format: ChannelFormat, sample_format: WavSampleFormat, tolerance: f32) {
+        let channels = format.get_channel_count().get();
+        let path = std::env::temp_dir().join(format!(
+            "synthizer_capture_test_{}_{}_{:?}.wav",
+            std::process::id(),
+            channels,
+            sample_format
+        ));
+
+        let input = (0..1000)
+            .flat_map(|i| {
+                let s = (i as f32 * 0.1).sin() * 0.8;
+                (0..channels).map(move |c| if c % 2 == 0 { s } else { -s })
+            })
+            .collect::<Vec<_>>();
+
+        {
+            let mut writer = WavWriter::new(&path, &format, sample_format, 44100).unwrap();
+            let split = 250 * channels;
+            writer.write(&input[..split]).unwrap();
+            writer.write(&input[split..]).unwrap();
+            // Dropping finalizes the header.
+        }
+
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let (got_channels, sr, output) = read_wav(&bytes);
+        assert_eq!(got_channels as usize, channels);
+        assert_eq!(sr, 44100);
+        assert_eq!(output.len(), input.len());
+        for (a, b) in input.iter().zip(output.iter()) {
+            close_floats32(*a, *b, tolerance);
+        }
+    }
+
+    #[test]
+    fn test_i16() {
+        check_format(
+            ChannelFormat::Stereo,
+            WavSampleFormat::I16,
+            1.0 / i16::MAX as f32,
+        );
+    }
+
+    #[test]
+    fn test_f32() {
+        check_format(ChannelFormat::Stereo, WavSampleFormat::F32, 1e-9);
+    }
+
+    #[test]
+    fn test_many_channels() {
+        let format = ChannelFormat::new_raw(6).unwrap();
+        check_format(format.clone(), WavSampleFormat::I16, 1.0 / i16::MAX as f32);
+        check_format(format, WavSampleFormat::F32, 1e-9);
+    }
+
+    #[test]
+    fn test_too_many_channels() {
+        let path = std::env::temp_dir().join(format!(
+            "synthizer_capture_test_{}_too_many.wav",
+            std::process::id()
+        ));
+
+        // More than are supported, and more than fit in the 16-bit channel count.
+        for channels in [crate::max_channels() + 1, 70000] {
+            let format = ChannelFormat::Raw {
+                channels: std::num::NonZeroUsize::new(channels).unwrap(),
+            };
+            let err = WavWriter::new(&path, &format, WavSampleFormat::I16, 44100)
+                .err()
+                .unwrap();
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        }
+
+        // The file isn't created for invalid formats.
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_finalize_at_size_limit() {
+        let path = std::env::temp_dir().join(format!(
+            "synthizer_capture_test_{}_limit.wav",
+            std::process::id()
+        ));
+
+        let mut writer =
+            WavWriter::new(&path, &ChannelFormat::Mono, WavSampleFormat::I16, 44100).unwrap();
+        // Pretend that the largest amount of data `write` allows has been written, without writing 4GB.
+        writer.data_bytes = u32::MAX as u64 - writer.data_size_offset;
+        let data_size_offset = writer.data_size_offset as usize;
+        writer.write(&[0.0]).unwrap_err();
+        writer.finish().unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(u32_at(&bytes, 4), u32::MAX - 4);
+        assert_eq!(
+            u32_at(&bytes, data_size_offset),
+            u32::MAX - data_size_offset as u32
+        );
+    }
+
+    /// Writing to /dev/full fails once the buffer is flushed, partway through a write.
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_poisoned_after_failed_write() {
+        let mut writer = WavWriter::new(
+            "/dev/full",
+            &ChannelFormat::Mono,
+            WavSampleFormat::I16,
+            44100,
+        )
+        .unwrap();
+
+        assert!(writer.write(&[0.0; 100000]).is_err());
+        assert!(writer.poisoned.is_some());
+        assert!(writer.data_bytes < 200000);
+        assert!(writer.write(&[0.0]).is_err());
+        assert!(writer.finish().is_err());
+    }
+}
@@ -0,0 +1,114 @@
+//! Tracking whether a value has changed.
+//!
+//! This is for control-side parameter objects which want to know whether they need to tell anything else about a new
+//! value, for example whether a command should be sent this tick.
+
+/// A value plus a flag saying whether it has changed since the flag was last cleared.
+///
+/// New values start clean.
+#[derive(Clone, Debug, Default)]
+pub struct ChangeTracked<T> {
+    value: T,
+    dirty: bool,
+}
+
+impl<T> ChangeTracked<T> {
+    pub fn new(value: T) -> ChangeTracked<T> {
+        ChangeTracked {
+            value,
+            dirty: false,
+        }
+    }
+
+    pub fn get(&self) -> &T {
+        &self.value
+    }
+
+    /// Replace the value, marking this tracker dirty even if the new value is the same as the old one.
+    pub fn set(&mut self, value: T) {
+        self.value = value;
+        self.dirty = true;
+    }
+
+    /// Mutate the value in place, marking this tracker dirty.
+    pub fn modify<R>(&mut self, f: impl FnOnce(&mut T) -> R) -> R {
+        self.dirty = true;
+        f(&mut self.value)
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    pub fn clear_dirty(&mut self) {
+        self.dirty = false;
+    }
+
+    /// If the value has changed, clear the dirty flag and return the value.
+    pub fn take_if_dirty(&mut self) -> Option<&T> {
+        if std::mem::replace(&mut self.dirty, false) {
+            Some(&self.value)
+        } else {
+            None
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T: PartialEq> ChangeTracked<T> {
+    /// Replace the value only if it differs from the current one, returning whether it did.
+    ///
+    /// Unlike [ChangeTracked::set], setting an equal value doesn't mark the tracker dirty.
+    pub fn set_if_changed(&mut self, value: T) -> bool {
+        if self.value == value {
+            return false;
+        }
+
+        self.set(value);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dirty_tracking() {
+        let mut tracked = ChangeTracked::new(1);
+        assert!(!tracked.is_dirty());
+        assert_eq!(tracked.take_if_dirty(), None);
+
+        tracked.set(2);
+        assert!(tracked.is_dirty());
+        assert_eq!(*tracked.get(), 2);
+        tracked.clear_dirty();
+        assert!(!tracked.is_dirty());
+
+        tracked.modify(|x| *x += 1);
+        assert_eq!(tracked.take_if_dirty(), Some(&3));
+        assert_eq!(tracked.take_if_dirty(), None);
+    }
+
+    #[test]
+    fn test_equal_values() {
+        let mut tracked = ChangeTracked::new(1);
+
+        assert!(!tracked.set_if_changed(1));
+        assert!(!tracked.is_dirty());
+        assert!(tracked.set_if_changed(2));
+        assert!(tracked.is_dirty());
+
+        // Plain set always dirties.
+        tracked.clear_dirty();
+        tracked.set(2);
+        assert!(tracked.is_dirty());
+    }
+}
@@ -1,3 +1,7 @@
+use cond_tree::{diverge_fn, MaybeInt};
+
+use crate::channel_format::ChannelFormatError;
+use crate::config::MAX_CHANNELS;
 use crate::views::*;
 use crate::ChannelFormat;
 
@@ -30,6 +34,9 @@ pub enum ChannelConversionError {
 
     #[error("The output format is raw, but the input isn't")]
     OnlyOutputRaw,
+
+    #[error(transparent)]
+    InvalidFormat(#[from] ChannelFormatError),
 }
 
 impl ChannelConverter {
@@ -40,6 +47,9 @@ impl ChannelConverter {
     ) -> Result<ChannelConverter, ChannelConversionError> {
         use ChannelFormat as Ch;
 
+        input_format.validate()?;
+        output_format.validate()?;
+
         match (&input_format, &output_format) {
             (Ch::Raw { .. }, x) if !x.is_raw() => return Err(ChannelConversionError::OnlyInputRaw),
             (x, Ch::Raw { .. }) if !x.is_raw() => {
@@ -135,6 +145,65 @@ fn raw_to_raw<
     }
 }
 
+/// Interleave some blocks of audio, one block per channel, into `out`.
+///
+/// # Panics
+///
+/// Panics if there are no blocks or more than [crate::max_channels] blocks, if the blocks are not all the same length,
+/// or if `out` is not exactly long enough to hold all of the blocks.
+pub fn interleave(blocks: &[&[f32]], out: &mut [f32]) {
+    let channels = blocks.len();
+    assert!((1..=MAX_CHANNELS).contains(&channels));
+    let frames = blocks[0].len();
+    assert!(blocks.iter().all(|b| b.len() == frames));
+    assert_eq!(out.len(), frames * channels);
+
+    interleave_inner(blocks, (channels as u16).into(), out);
+}
+
+#[diverge_fn]
+fn interleave_inner(blocks: &[&[f32]], channels: MaybeInt<u16, 2>, out: &mut [f32]) {
+    #[diverge(channels)]
+    {
+        let channels = channels.get() as usize;
+        for (ch, block) in blocks.iter().enumerate() {
+            for (frame, s) in block.iter().enumerate() {
+                out[frame * channels + ch] = *s;
+            }
+        }
+    }
+}
+
+/// Deinterleave audio with `channels` channels into `out`, one block per channel.
+///
+/// # Panics
+///
+/// Panics if `channels` is 0 or above [crate::max_channels], if `out` doesn't contain exactly `channels` blocks, if
+/// `interleaved` isn't a multiple of `channels` in length, or if any block in `out` isn't exactly one frame per
+/// interleaved frame in length.
+pub fn deinterleave(interleaved: &[f32], channels: usize, out: &mut [&mut [f32]]) {
+    assert!((1..=MAX_CHANNELS).contains(&channels));
+    assert_eq!(out.len(), channels);
+    assert_eq!(interleaved.len() % channels, 0);
+    let frames = interleaved.len() / channels;
+    assert!(out.iter().all(|b| b.len() == frames));
+
+    deinterleave_inner(interleaved, (channels as u16).into(), out);
+}
+
+#[diverge_fn]
+fn deinterleave_inner(interleaved: &[f32], channels: MaybeInt<u16, 2>, out: &mut [&mut [f32]]) {
+    #[diverge(channels)]
+    {
+        let channels = channels.get() as usize;
+        for (ch, block) in out.iter_mut().enumerate() {
+            for (frame, s) in block.iter_mut().enumerate() {
+                *s = interleaved[frame * channels + ch];
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -216,4 +285,54 @@ mod tests {
         );
         assert_eq!(output, [1.0, 2.0, 0.0, 3.0, 4.0, 0.0, 5.0, 6.0, 0.0]);
     }
+
+    #[test]
+    fn test_rejects_too_many_channels() {
+        let too_many = ChannelFormat::Raw {
+            channels: NonZeroUsize::new(crate::max_channels() + 1).unwrap(),
+        };
+        let ok = ChannelFormat::new_raw(2).unwrap();
+
+        for (i, o) in [(too_many.clone(), ok.clone()), (ok, too_many)] {
+            assert!(matches!(
+                ChannelConverter::new(i, o),
+                Err(ChannelConversionError::InvalidFormat(
+                    ChannelFormatError::TooManyChannels { .. }
+                ))
+            ));
+        }
+    }
+
+    fn round_trip(channels: usize) {
+        let blocks = (0..channels)
+            .map(|ch| (0..7).map(|f| (ch * 100 + f) as f32).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let block_refs = blocks.iter().map(|b| &b[..]).collect::<Vec<_>>();
+
+        let mut interleaved = vec![0.0f32; 7 * channels];
+        interleave(&block_refs, &mut interleaved);
+        for (i, s) in interleaved.iter().enumerate() {
+            assert_eq!(*s, ((i % channels) * 100 + i / channels) as f32);
+        }
+
+        let mut got = vec![vec![0.0f32; 7]; channels];
+        let mut got_refs = got.iter_mut().map(|b| &mut b[..]).collect::<Vec<_>>();
+        deinterleave(&interleaved, channels, &mut got_refs);
+        assert_eq!(got, blocks);
+    }
+
+    #[test]
+    fn test_interleave_round_trip() {
+        for channels in [1, 2, 3, 5] {
+            round_trip(channels);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_deinterleave_bad_length() {
+        let mut a = [0.0f32; 2];
+        let mut b = [0.0f32; 2];
+        deinterleave(&[0.0; 5], 2, &mut [&mut a[..], &mut b[..]]);
+    }
 }
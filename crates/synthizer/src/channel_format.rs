@@ -1,7 +1,14 @@
+use std::fmt::Display;
 use std::num::NonZeroUsize;
+use std::str::FromStr;
+
+use crate::config::MAX_CHANNELS;
 
 /// A format for audio data.
-#[derive(Clone, Debug, derive_more::IsVariant)]
+///
+/// Formats may be converted to and from strings: `mono`, `stereo`, or `raw:N` for a raw format with `N` channels.  With
+/// the `serde` feature, formats are serialized as these strings.
+#[derive(Clone, Debug, Eq, PartialEq, derive_more::IsVariant)]
 pub enum ChannelFormat {
     /// This is single-channel mono audio.
     Mono,
@@ -10,10 +17,62 @@ pub enum ChannelFormat {
     Stereo,
 
     /// This is some raw audio data without an interpretation.
+    ///
+    /// Prefer [ChannelFormat::new_raw], which checks the channel count.  Formats built directly are checked when they
+    /// are used, see [ChannelFormat::validate].
     Raw { channels: NonZeroUsize },
 }
 
+/// Reasons a channel format may be invalid.
+#[derive(Debug, thiserror::Error)]
+pub enum ChannelFormatError {
+    #[error("Channel formats must have at least one channel")]
+    NoChannels,
+
+    #[error("Got {requested} channels, but at most {max} are supported")]
+    TooManyChannels { requested: usize, max: usize },
+}
+
+/// Reasons a string could not be parsed as a [ChannelFormat].
+#[derive(Debug, thiserror::Error)]
+pub enum ParseChannelFormatError {
+    #[error("Unknown channel format {0:?}: expected mono, stereo, or raw:N")]
+    UnknownFormat(String),
+
+    #[error("Could not parse the channel count of {0:?}")]
+    BadChannelCount(String),
+
+    #[error(transparent)]
+    InvalidFormat(#[from] ChannelFormatError),
+}
+
 impl ChannelFormat {
+    /// Build a [ChannelFormat::Raw], validating that the channel count is supported.
+    ///
+    /// See [crate::max_channels].
+    pub fn new_raw(channels: usize) -> Result<ChannelFormat, ChannelFormatError> {
+        let channels = NonZeroUsize::new(channels).ok_or(ChannelFormatError::NoChannels)?;
+        let ret = ChannelFormat::Raw { channels };
+        ret.validate()?;
+        Ok(ret)
+    }
+
+    /// Check that this format's channel count is supported.
+    ///
+    /// [ChannelFormat::Raw] may be constructed directly with any channel count, so everything which takes a format
+    /// checks it with this.
+    pub fn validate(&self) -> Result<(), ChannelFormatError> {
+        let channels = self.get_channel_count().get();
+        if channels > MAX_CHANNELS {
+            return Err(ChannelFormatError::TooManyChannels {
+                requested: channels,
+                max: MAX_CHANNELS,
+            });
+        }
+
+        Ok(())
+    }
+
     pub fn get_channel_count(&self) -> NonZeroUsize {
         match self {
             ChannelFormat::Mono => NonZeroUsize::new(1).unwrap(),
@@ -22,3 +81,161 @@ impl ChannelFormat {
         }
     }
 }
+
+impl Display for ChannelFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChannelFormat::Mono => write!(f, "mono"),
+            ChannelFormat::Stereo => write!(f, "stereo"),
+            ChannelFormat::Raw { channels } => write!(f, "raw:{}", channels),
+        }
+    }
+}
+
+impl FromStr for ChannelFormat {
+    type Err = ParseChannelFormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        if trimmed.eq_ignore_ascii_case("mono") {
+            return Ok(ChannelFormat::Mono);
+        } else if trimmed.eq_ignore_ascii_case("stereo") {
+            return Ok(ChannelFormat::Stereo);
+        }
+
+        let Some((kind, channels)) = trimmed.split_once(':') else {
+            return Err(ParseChannelFormatError::UnknownFormat(s.to_string()));
+        };
+        if !kind.trim().eq_ignore_ascii_case("raw") {
+            return Err(ParseChannelFormatError::UnknownFormat(s.to_string()));
+        }
+
+        let channels: usize = channels
+            .trim()
+            .parse()
+            .map_err(|_| ParseChannelFormatError::BadChannelCount(s.to_string()))?;
+        Ok(ChannelFormat::new_raw(channels)?)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for ChannelFormat {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for ChannelFormat {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_raw() {
+        assert_eq!(
+            ChannelFormat::new_raw(5).unwrap().get_channel_count().get(),
+            5
+        );
+        assert_eq!(
+            ChannelFormat::new_raw(crate::max_channels())
+                .unwrap()
+                .get_channel_count()
+                .get(),
+            crate::max_channels()
+        );
+        assert!(matches!(
+            ChannelFormat::new_raw(0),
+            Err(ChannelFormatError::NoChannels)
+        ));
+        assert!(matches!(
+            ChannelFormat::new_raw(crate::max_channels() + 1),
+            Err(ChannelFormatError::TooManyChannels { .. })
+        ));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(ChannelFormat::Mono.validate().is_ok());
+        assert!(ChannelFormat::Stereo.validate().is_ok());
+        let too_many = ChannelFormat::Raw {
+            channels: NonZeroUsize::new(crate::max_channels() + 1).unwrap(),
+        };
+        assert!(matches!(
+            too_many.validate(),
+            Err(ChannelFormatError::TooManyChannels { .. })
+        ));
+    }
+
+    #[test]
+    fn test_string_round_trip() {
+        for format in [
+            ChannelFormat::Mono,
+            ChannelFormat::Stereo,
+            ChannelFormat::new_raw(1).unwrap(),
+            ChannelFormat::new_raw(8).unwrap(),
+        ] {
+            let s = format.to_string();
+            assert_eq!(s.parse::<ChannelFormat>().unwrap(), format);
+        }
+
+        assert_eq!(
+            " Stereo ".parse::<ChannelFormat>().unwrap(),
+            ChannelFormat::Stereo
+        );
+        assert_eq!(
+            "raw:8".parse::<ChannelFormat>().unwrap(),
+            ChannelFormat::new_raw(8).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(matches!(
+            "5.1".parse::<ChannelFormat>(),
+            Err(ParseChannelFormatError::UnknownFormat(_))
+        ));
+        assert!(matches!(
+            "cooked:2".parse::<ChannelFormat>(),
+            Err(ParseChannelFormatError::UnknownFormat(_))
+        ));
+        assert!(matches!(
+            "raw:many".parse::<ChannelFormat>(),
+            Err(ParseChannelFormatError::BadChannelCount(_))
+        ));
+        assert!(matches!(
+            "raw:0".parse::<ChannelFormat>(),
+            Err(ParseChannelFormatError::InvalidFormat(
+                ChannelFormatError::NoChannels
+            ))
+        ));
+        assert!(matches!(
+            "raw:1000".parse::<ChannelFormat>(),
+            Err(ParseChannelFormatError::InvalidFormat(
+                ChannelFormatError::TooManyChannels { .. }
+            ))
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let formats = vec![
+            ChannelFormat::Mono,
+            ChannelFormat::Stereo,
+            ChannelFormat::new_raw(4).unwrap(),
+        ];
+        let json = serde_json::to_string(&formats).unwrap();
+        assert_eq!(json, r#"["mono","stereo","raw:4"]"#);
+        let back: Vec<ChannelFormat> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, formats);
+
+        assert!(serde_json::from_str::<ChannelFormat>(r#""quad""#).is_err());
+    }
+}
@@ -21,5 +21,12 @@ pub(crate) const BLOCK_SIZE: usize = 128;
 /// wish to in future, and it makes as good a value as any.
 pub(crate) const MAX_CHANNELS: usize = 16;
 
+/// Get the maximum number of channels which the library supports.
+///
+/// This may be used to validate formats before handing them to the library.
+pub const fn max_channels() -> usize {
+    MAX_CHANNELS
+}
+
 /// The length of a "channel block".  This is a convenience constant `BLOCK_SIZE * MAX_CHANNELS` which can be used for arrays that want to inline their data.
 pub(crate) const CHANNEL_BLOCK_LEN: usize = MAX_CHANNELS * BLOCK_SIZE;
@@ -28,6 +28,25 @@ macro_rules! db_impl {
 db_impl!(f32);
 db_impl!(f64);
 
+/// Get the gain for a fade which is linear in the dB domain.
+///
+/// `progress` is clamped to `0.0..=1.0`, where 0.0 is `start_db` and 1.0 is `end_db`.  The returned value is a linear
+/// gain suitable for multiplying samples with.
+pub fn db_fade_gain(progress: f64, start_db: f64, end_db: f64) -> f64 {
+    let progress = progress.clamp(0.0, 1.0);
+    (start_db + (end_db - start_db) * progress).db_to_gain()
+}
+
+/// Get the gains for a constant-power crossfade.
+///
+/// `progress` is clamped to `0.0..=1.0`.  Returns `(outgoing, incoming)`: at 0.0 this is `(1.0, 0.0)` and at 1.0 this
+/// is `(0.0, 1.0)`.  The squares of the gains always sum to 1, so uncorrelated signals keep the same perceived
+/// loudness over the fade.
+pub fn equal_power_crossfade(progress: f64) -> (f64, f64) {
+    let angle = progress.clamp(0.0, 1.0) * std::f64::consts::FRAC_PI_2;
+    (angle.cos(), angle.sin())
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -41,4 +60,33 @@ mod tests {
         close_floats64((-6.0f64).db_to_gain(), 0.5, 0.03);
         close_floats32((-6.0f32).db_to_gain(), 0.5, 0.03);
     }
+
+    #[test]
+    fn test_db_fade_gain() {
+        use crate::close_floats::*;
+
+        use super::*;
+
+        close_floats64(db_fade_gain(0.0, 0.0, -12.0), 1.0, 0.001);
+        close_floats64(db_fade_gain(0.5, 0.0, -12.0), 0.5, 0.03);
+        close_floats64(db_fade_gain(1.0, 0.0, -12.0), 0.25, 0.03);
+        close_floats64(db_fade_gain(2.0, 0.0, -12.0), 0.25, 0.03);
+    }
+
+    #[test]
+    fn test_equal_power_crossfade() {
+        use crate::close_floats::*;
+
+        use super::*;
+
+        assert_eq!(equal_power_crossfade(0.0), (1.0, 0.0));
+        let (a, b) = equal_power_crossfade(1.0);
+        close_floats64(a, 0.0, 0.0001);
+        close_floats64(b, 1.0, 0.0001);
+
+        for i in 0..=100 {
+            let (a, b) = equal_power_crossfade(i as f64 / 100.0);
+            close_floats64(a * a + b * b, 1.0, 0.0001);
+        }
+    }
 }
@@ -0,0 +1,124 @@
+//! Table-based approximations of transcendental functions.
+//!
+//! Phases here are normalized: `0.0..1.0` is one period, and phases outside that range wrap.  Linear interpolation
+//! over `n` segments of a period has an error of at most `pi^2 / (2 * n^2)`; for the built-in 1024-segment table used
+//! by [fast_sin] and [fast_cos] that is under `5e-6`, or about -106 dB.
+use std::f64::consts::PI;
+
+include!(concat!(env!("OUT_DIR"), "/sin_table.rs"));
+
+/// The maximum error of [fast_sin] and [fast_cos].
+pub const FAST_TRIG_MAX_ERROR: f64 =
+    PI * PI / (2.0 * ((SIN_TABLE.len() - 1) * (SIN_TABLE.len() - 1)) as f64);
+
+/// Interpolate a table holding one period of a function plus a guard point.
+#[inline(always)]
+fn interpolate(table: &[f64], phase: f64) -> f64 {
+    let segments = table.len() - 1;
+    let pos = (phase - phase.floor()) * segments as f64;
+    // Phases just under 1.0 can round up to exactly `segments`, so clamp.
+    let index = (pos as usize).min(segments - 1);
+    let frac = pos - index as f64;
+    let a = table[index];
+    let b = table[index + 1];
+    a + (b - a) * frac
+}
+
+/// A precomputed table of one period of `sin`, for when the built-in table's accuracy isn't appropriate.
+#[derive(Debug, Clone)]
+pub struct SinTable {
+    table: Box<[f64]>,
+}
+
+impl SinTable {
+    /// Build a table with `segments` linear segments over one period.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `segments` is not a power of 2 or is less than 4.
+    pub fn new(segments: usize) -> SinTable {
+        assert!(segments.is_power_of_two() && segments >= 4);
+        let table = (0..=segments)
+            .map(|i| (2.0 * PI * (i % segments) as f64 / segments as f64).sin())
+            .collect();
+        SinTable { table }
+    }
+
+    /// Approximate `sin(2 * pi * phase)`.
+    pub fn sin(&self, phase: f64) -> f64 {
+        interpolate(&self.table, phase)
+    }
+
+    /// Approximate `cos(2 * pi * phase)`.
+    pub fn cos(&self, phase: f64) -> f64 {
+        interpolate(&self.table, phase + 0.25)
+    }
+
+    /// The maximum error of this table's approximations.
+    pub fn max_error(&self) -> f64 {
+        let segments = (self.table.len() - 1) as f64;
+        PI * PI / (2.0 * segments * segments)
+    }
+}
+
+/// Approximate `sin(2 * pi * phase)` to within [FAST_TRIG_MAX_ERROR].
+pub fn fast_sin(phase: f64) -> f64 {
+    interpolate(&SIN_TABLE, phase)
+}
+
+/// Approximate `cos(2 * pi * phase)` to within [FAST_TRIG_MAX_ERROR].
+pub fn fast_cos(phase: f64) -> f64 {
+    interpolate(&SIN_TABLE, phase + 0.25)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STEPS: usize = 100000;
+
+    fn phases() -> impl Iterator<Item = f64> {
+        (0..STEPS).map(|i| i as f64 / STEPS as f64)
+    }
+
+    #[test]
+    fn test_fast_trig_accuracy() {
+        for phase in phases() {
+            let angle = 2.0 * PI * phase;
+            let sin_err = (fast_sin(phase) - angle.sin()).abs();
+            let cos_err = (fast_cos(phase) - angle.cos()).abs();
+            assert!(
+                sin_err <= FAST_TRIG_MAX_ERROR,
+                "phase={} err={}",
+                phase,
+                sin_err
+            );
+            assert!(
+                cos_err <= FAST_TRIG_MAX_ERROR,
+                "phase={} err={}",
+                phase,
+                cos_err
+            );
+        }
+    }
+
+    #[test]
+    fn test_table_accuracy() {
+        for segments in [16, 256] {
+            let table = SinTable::new(segments);
+            for phase in phases() {
+                let angle = 2.0 * PI * phase;
+                assert!((table.sin(phase) - angle.sin()).abs() <= table.max_error());
+                assert!((table.cos(phase) - angle.cos()).abs() <= table.max_error());
+            }
+        }
+    }
+
+    #[test]
+    fn test_phase_wraps() {
+        for phase in [0.1, 0.5, 0.9] {
+            assert!((fast_sin(phase) - fast_sin(phase + 3.0)).abs() < 1e-12);
+            assert!((fast_sin(phase) - fast_sin(phase - 2.0)).abs() < 1e-12);
+        }
+    }
+}
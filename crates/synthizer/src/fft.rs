@@ -0,0 +1,138 @@
+//! A wrapper over `rustfft` which caches plans and scratch space.
+//!
+//! Planning an FFT is expensive, so [Fft] keeps the plans for every size it has seen and reuses them.  The first
+//! transform of any given size allocates; later ones only allocate the returned buffer.
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use num::complex::Complex32;
+use rustfft::FftPlanner;
+
+struct Plans {
+    forward: Arc<dyn rustfft::Fft<f32>>,
+    inverse: Arc<dyn rustfft::Fft<f32>>,
+}
+
+struct FftCache {
+    planner: FftPlanner<f32>,
+    plans: HashMap<usize, Plans>,
+    scratch: Vec<Complex32>,
+}
+
+/// Forward and inverse FFTs of real signals, with cached plans.
+///
+/// Transforms take `&self` and update the caches through a `RefCell`, so an `Fft` can be used from one thread at a
+/// time.
+pub struct Fft {
+    cache: RefCell<FftCache>,
+}
+
+impl FftCache {
+    fn get_plans(&mut self, len: usize) -> &Plans {
+        let planner = &mut self.planner;
+        self.plans.entry(len).or_insert_with(|| Plans {
+            forward: planner.plan_fft_forward(len),
+            inverse: planner.plan_fft_inverse(len),
+        })
+    }
+}
+
+impl Fft {
+    pub fn new() -> Fft {
+        Fft {
+            cache: RefCell::new(FftCache {
+                planner: FftPlanner::new(),
+                plans: HashMap::new(),
+                scratch: vec![],
+            }),
+        }
+    }
+
+    fn process(&self, buffer: &mut [Complex32], forward: bool) {
+        let mut cache = self.cache.borrow_mut();
+        let plans = cache.get_plans(buffer.len());
+        let plan = if forward {
+            plans.forward.clone()
+        } else {
+            plans.inverse.clone()
+        };
+
+        cache
+            .scratch
+            .resize(plan.get_inplace_scratch_len(), Complex32::default());
+        plan.process_with_scratch(buffer, &mut cache.scratch[..]);
+    }
+
+    /// Compute the FFT of a real signal.
+    ///
+    /// The output is the full complex spectrum, the same length as the input.
+    pub fn forward(&self, input: &[f32]) -> Vec<Complex32> {
+        let mut buffer = input
+            .iter()
+            .map(|x| Complex32::new(*x, 0.0))
+            .collect::<Vec<_>>();
+        self.process(&mut buffer[..], true);
+        buffer
+    }
+
+    /// Compute the inverse FFT of a spectrum, returning the real part.
+    ///
+    /// The output is normalized by `1 / len`, so `inverse(forward(x))` is `x`.
+    pub fn inverse(&self, input: &[Complex32]) -> Vec<f32> {
+        let mut buffer = input.to_vec();
+        self.process(&mut buffer[..], false);
+        let norm = 1.0 / input.len() as f32;
+        buffer.iter().map(|x| x.re * norm).collect()
+    }
+}
+
+impl Default for Fft {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::close_floats::*;
+
+    #[test]
+    fn test_round_trip() {
+        let fft = Fft::new();
+
+        for len in [16usize, 100, 1024] {
+            let input = (0..len)
+                .map(|i| ((i * 7919) % 101) as f32 / 50.0 - 1.0)
+                .collect::<Vec<_>>();
+            let spectrum = fft.forward(&input);
+            assert_eq!(spectrum.len(), len);
+            let output = fft.inverse(&spectrum);
+            for (a, b) in input.iter().zip(output.iter()) {
+                close_floats32(*a, *b, 0.0001);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sine_has_one_dominant_bin() {
+        let fft = Fft::new();
+        let len = 256;
+        let bin = 10;
+        let input = (0..len)
+            .map(|i| (2.0 * std::f32::consts::PI * bin as f32 * i as f32 / len as f32).sin())
+            .collect::<Vec<_>>();
+        let spectrum = fft.forward(&input);
+
+        // A real sine puts half its energy in the bin and half in the mirrored bin.
+        for (i, c) in spectrum.iter().enumerate() {
+            if i == bin || i == len - bin {
+                close_floats32(c.norm(), len as f32 / 2.0, 0.01);
+            } else {
+                close_floats32(c.norm(), 0.0, 0.01);
+            }
+        }
+    }
+}
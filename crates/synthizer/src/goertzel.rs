@@ -0,0 +1,128 @@
+//! The Goertzel algorithm, for measuring a signal at one frequency.
+//!
+//! This is much cheaper than an FFT when only one or a few frequencies are of interest, for example detecting a
+//! calibration tone.
+use std::f64::consts::PI;
+
+/// Detects a single frequency over blocks of samples.
+///
+/// Samples are fed in with [Goertzel::process], which may be called with any number of samples at a time.  Every
+/// `block_size` samples the detector computes a magnitude and resets.  Magnitudes are normalized so that a sine of
+/// amplitude `a` at the target frequency reads as `a`.  For the cleanest separation of nearby frequencies, the target
+/// should fall close to a multiple of `sample_rate / block_size`.
+#[derive(Debug, Clone)]
+pub struct Goertzel {
+    coeff: f64,
+    block_size: usize,
+
+    s1: f64,
+    s2: f64,
+    processed: usize,
+    last_magnitude: f64,
+}
+
+impl Goertzel {
+    /// # Panics
+    ///
+    /// Panics if `block_size` is 0, or if `target_hz` isn't between 0 and nyquist.
+    pub fn new(target_hz: f64, sample_rate: u32, block_size: usize) -> Goertzel {
+        assert!(block_size > 0, "Blocks must contain at least one sample");
+        assert!(
+            (0.0..=sample_rate as f64 / 2.0).contains(&target_hz),
+            "The target must be between 0 and nyquist"
+        );
+
+        let omega = 2.0 * PI * target_hz / sample_rate as f64;
+        Goertzel {
+            coeff: 2.0 * omega.cos(),
+            block_size,
+            s1: 0.0,
+            s2: 0.0,
+            processed: 0,
+            last_magnitude: 0.0,
+        }
+    }
+
+    /// Feed some samples through the detector, returning the magnitude of the most recently completed block.
+    ///
+    /// Returns 0.0 until the first block completes.
+    pub fn process(&mut self, samples: &[f64]) -> f64 {
+        for s in samples.iter().copied() {
+            let s0 = s + self.coeff * self.s1 - self.s2;
+            self.s2 = self.s1;
+            self.s1 = s0;
+            self.processed += 1;
+
+            if self.processed == self.block_size {
+                let power = self.s1 * self.s1 + self.s2 * self.s2 - self.coeff * self.s1 * self.s2;
+                self.last_magnitude = 2.0 * power.max(0.0).sqrt() / self.block_size as f64;
+                self.reset_block();
+            }
+        }
+
+        self.last_magnitude
+    }
+
+    /// The magnitude of the most recently completed block, or 0.0 if no block has completed.
+    pub fn magnitude(&self) -> f64 {
+        self.last_magnitude
+    }
+
+    /// Throw away any partial block and the last magnitude.
+    pub fn reset(&mut self) {
+        self.reset_block();
+        self.last_magnitude = 0.0;
+    }
+
+    fn reset_block(&mut self) {
+        self.s1 = 0.0;
+        self.s2 = 0.0;
+        self.processed = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::close_floats::*;
+
+    const SR: u32 = 44100;
+    const BLOCK: usize = 441;
+
+    fn sine(freq: f64, amplitude: f64, len: usize) -> Vec<f64> {
+        (0..len)
+            .map(|i| amplitude * (2.0 * PI * freq * i as f64 / SR as f64).sin())
+            .collect()
+    }
+
+    #[test]
+    fn test_detects_target() {
+        // 441-sample blocks put bins every 100 hz.
+        let mut det = Goertzel::new(1000.0, SR, BLOCK);
+        let mag = det.process(&sine(1000.0, 0.5, BLOCK));
+        close_floats64(mag, 0.5, 1e-6);
+    }
+
+    #[test]
+    fn test_rejects_other_frequencies() {
+        for other in [700.0, 1500.0, 5000.0] {
+            let mut det = Goertzel::new(1000.0, SR, BLOCK);
+            let mag = det.process(&sine(other, 0.5, BLOCK));
+            assert!(mag < 0.01, "{} hz gave {}", other, mag);
+        }
+    }
+
+    #[test]
+    fn test_split_input() {
+        let input = sine(1000.0, 1.0, BLOCK * 2);
+        let mut whole = Goertzel::new(1000.0, SR, BLOCK);
+        let mut split = Goertzel::new(1000.0, SR, BLOCK);
+
+        assert_eq!(split.process(&input[..100]), 0.0);
+        for chunk in input[100..].chunks(37) {
+            split.process(chunk);
+        }
+        close_floats64(split.magnitude(), whole.process(&input), 1e-9);
+    }
+}
@@ -1,6 +1,8 @@
 #![allow(dead_code)]
 pub mod biquad;
 pub(crate) mod block_stream_conversion;
+pub mod capture;
+pub mod change_tracker;
 pub mod channel_conversion;
 mod channel_format;
 #[cfg(test)]
@@ -9,13 +11,21 @@ mod config;
 pub mod convolution;
 mod db;
 pub mod fast_xoroshiro;
+pub mod fastmath;
+pub mod fft;
+pub mod goertzel;
+pub mod notes;
+pub mod splittable_buffer;
+pub mod svf;
 mod time;
 mod unique_id;
 pub mod views;
+pub mod window;
 
+pub use change_tracker::ChangeTracked;
 pub use channel_conversion::ChannelConverter;
 pub use channel_format::*;
-pub use config::SR;
-pub use db::DbExt;
+pub use config::{max_channels, SR};
+pub use db::{db_fade_gain, equal_power_crossfade, DbExt};
 pub use time::*;
 pub use views::{OutputView, ViewMeta};
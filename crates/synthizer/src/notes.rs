@@ -0,0 +1,95 @@
+//! Musical notes, for specifying pitch by MIDI note number or name rather than hz.
+//!
+//! Tuning is 12-tone equal temperament with A4 (MIDI note 69) at 440 hz.  Octaves are numbered so that middle C is C4,
+//! so MIDI note 0 is C-1.
+
+/// Convert a MIDI note number to a frequency in hz.
+///
+/// Fractional note numbers are allowed, and give frequencies between the semitones.
+pub fn note_hz(midi: f64) -> f64 {
+    440.0 * 2.0f64.powf((midi - 69.0) / 12.0)
+}
+
+/// Parse a note name such as `A4`, `C#5`, `Bb3`, or `C-1` to a MIDI note number.
+///
+/// The letter is case-insensitive, and may be followed by any number of `#` or `b` accidentals.  Returns `None` if the
+/// name can't be parsed or is outside the MIDI range of 0 to 127.
+pub fn note_name_to_midi(name: &str) -> Option<u8> {
+    let name = name.trim();
+    let mut chars = name.chars();
+
+    let semitone: i32 = match chars.next()?.to_ascii_uppercase() {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return None,
+    };
+
+    let rest = chars.as_str();
+    let octave_start = rest.find(|c: char| c != '#' && c != 'b')?;
+    let (accidentals, octave) = rest.split_at(octave_start);
+    let accidental = accidentals.chars().try_fold(0i32, |acc, c| {
+        acc.checked_add(if c == '#' { 1 } else { -1 })
+    })?;
+    let octave: i32 = octave.parse().ok()?;
+
+    let midi = octave
+        .checked_add(1)?
+        .checked_mul(12)?
+        .checked_add(semitone)?
+        .checked_add(accidental)?;
+    u8::try_from(midi).ok().filter(|m| *m <= 127)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::close_floats::*;
+
+    #[test]
+    fn test_note_hz() {
+        close_floats64(note_hz(69.0), 440.0, 1e-9);
+        close_floats64(note_hz(81.0), 880.0, 1e-9);
+        close_floats64(note_hz(57.0), 220.0, 1e-9);
+        // Middle C.
+        close_floats64(note_hz(60.0), 261.6256, 1e-4);
+
+        for n in [0.0, 30.5, 100.0] {
+            close_floats64(note_hz(n + 12.0), note_hz(n) * 2.0, 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_note_name_to_midi() {
+        assert_eq!(note_name_to_midi("A4"), Some(69));
+        assert_eq!(note_name_to_midi("a4"), Some(69));
+        assert_eq!(note_name_to_midi("C4"), Some(60));
+        assert_eq!(note_name_to_midi("C#4"), Some(61));
+        assert_eq!(note_name_to_midi("Db4"), Some(61));
+        assert_eq!(note_name_to_midi("Bb3"), Some(58));
+        assert_eq!(note_name_to_midi("B#3"), Some(60));
+        assert_eq!(note_name_to_midi("Cb4"), Some(59));
+        assert_eq!(note_name_to_midi("C##4"), Some(62));
+        assert_eq!(note_name_to_midi("C-1"), Some(0));
+        assert_eq!(note_name_to_midi("G9"), Some(127));
+
+        assert_eq!(note_name_to_midi("Cb-1"), None);
+        assert_eq!(note_name_to_midi("G#9"), None);
+        assert_eq!(note_name_to_midi("H4"), None);
+        assert_eq!(note_name_to_midi("A"), None);
+        assert_eq!(note_name_to_midi("A#"), None);
+        assert_eq!(note_name_to_midi("A4x"), None);
+
+        // Extreme octaves must not overflow.
+        assert_eq!(note_name_to_midi("B178956969"), None);
+        assert_eq!(note_name_to_midi("Cb-178956971"), None);
+        assert_eq!(note_name_to_midi("C2147483647"), None);
+        assert_eq!(note_name_to_midi("C-2147483648"), None);
+        assert_eq!(note_name_to_midi(""), None);
+    }
+}
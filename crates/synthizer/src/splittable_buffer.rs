@@ -0,0 +1,222 @@
+//! A buffer which may be split into disjoint mutable parts and put back together.
+//!
+//! This is for block processing which hands sub-ranges of one buffer to different processors, possibly on different
+//! threads, without copying.
+//!
+//! # Safety model
+//!
+//! Splitting is `slice::split_at_mut` underneath: every [BufferPart] is a unique borrow of a range of the buffer, and
+//! no two parts overlap.  Each part also remembers the base of the buffer it came from and its offset within it.
+//! Recombining two parts checks that they came from the same buffer and are adjacent, and only then rebuilds one slice
+//! spanning both from the buffer's base pointer.  The combined slice covers exactly the memory the two parts already
+//! uniquely borrowed, for the same lifetime, so no aliasing is introduced.  All of the parts borrow the
+//! [SplittableBuffer] mutably, so the buffer can't be touched again until every part is gone.
+
+/// An owned buffer which may be split into disjoint parts.
+#[derive(Clone, Debug, Default)]
+pub struct SplittableBuffer<T> {
+    data: Vec<T>,
+}
+
+/// A mutable view of part of a [SplittableBuffer].
+///
+/// Parts dereference to slices, and may be split further or recombined with adjacent parts.
+#[derive(Debug)]
+pub struct BufferPart<'a, T> {
+    data: &'a mut [T],
+
+    /// The start of the buffer this part came from.
+    ///
+    /// This is used to check that recombined parts came from the same buffer, and is what recombined slices are built
+    /// from, since it is valid for the whole buffer rather than only one part.
+    base: *mut T,
+
+    /// Where this part starts in the buffer.
+    offset: usize,
+}
+
+// Parts are just mutable slices plus some bookkeeping; the base pointer is only used to rebuild ranges which parts
+// already own.
+unsafe impl<'a, T: Send> Send for BufferPart<'a, T> {}
+unsafe impl<'a, T: Sync> Sync for BufferPart<'a, T> {}
+
+impl<T> SplittableBuffer<T> {
+    pub fn new(data: Vec<T>) -> SplittableBuffer<T> {
+        SplittableBuffer { data }
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.data
+    }
+
+    /// Borrow the whole buffer as one part, ready to be split.
+    pub fn whole(&mut self) -> BufferPart<'_, T> {
+        let base = self.data.as_mut_ptr();
+        // Safety: this is the whole of the vec, which is borrowed mutably for as long as the part lives.
+        let data = unsafe { std::slice::from_raw_parts_mut(base, self.data.len()) };
+        BufferPart {
+            data,
+            base,
+            offset: 0,
+        }
+    }
+
+    /// Split the buffer in two at `mid`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mid > len`.
+    pub fn split_at_mut(&mut self, mid: usize) -> (BufferPart<'_, T>, BufferPart<'_, T>) {
+        self.whole().split_at_mut(mid)
+    }
+
+    pub fn into_inner(self) -> Vec<T> {
+        self.data
+    }
+}
+
+impl<T> From<Vec<T>> for SplittableBuffer<T> {
+    fn from(data: Vec<T>) -> Self {
+        SplittableBuffer::new(data)
+    }
+}
+
+impl<'a, T> BufferPart<'a, T> {
+    /// Where this part starts in the buffer it came from.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Split this part in two at `mid`, relative to the start of this part.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mid > len`.
+    pub fn split_at_mut(self, mid: usize) -> (BufferPart<'a, T>, BufferPart<'a, T>) {
+        let (left, right) = self.data.split_at_mut(mid);
+        (
+            BufferPart {
+                data: left,
+                base: self.base,
+                offset: self.offset,
+            },
+            BufferPart {
+                data: right,
+                base: self.base,
+                offset: self.offset + mid,
+            },
+        )
+    }
+
+    /// Put this part back together with the part which immediately follows it.
+    ///
+    /// If `next` came from a different buffer or doesn't start where this part ends, both parts are handed back.
+    pub fn recombine(
+        self,
+        next: BufferPart<'a, T>,
+    ) -> Result<BufferPart<'a, T>, (BufferPart<'a, T>, BufferPart<'a, T>)> {
+        if self.base != next.base || self.offset + self.data.len() != next.offset {
+            return Err((self, next));
+        }
+
+        let len = self.data.len() + next.data.len();
+        // Safety: both parts are unique borrows from the same buffer for 'a, and `next` starts exactly where `self`
+        // ends, so together they are one contiguous range of that buffer which nothing else can access.  The slice is
+        // built from the base pointer because the pointer of `self.data` is only valid for `self`'s range.
+        let data = unsafe { std::slice::from_raw_parts_mut(self.base.add(self.offset), len) };
+        Ok(BufferPart {
+            data,
+            base: self.base,
+            offset: self.offset,
+        })
+    }
+}
+
+impl<'a, T> std::ops::Deref for BufferPart<'a, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.data
+    }
+}
+
+impl<'a, T> std::ops::DerefMut for BufferPart<'a, T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        self.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_and_write_halves() {
+        let mut buffer = SplittableBuffer::new(vec![0u32; 100]);
+
+        {
+            let (mut left, mut right) = buffer.split_at_mut(40);
+            assert_eq!((left.offset(), left.len()), (0, 40));
+            assert_eq!((right.offset(), right.len()), (40, 60));
+
+            let fill_left = |part: &mut [u32]| part.iter_mut().for_each(|x| *x = 1);
+            let fill_right = |part: &mut [u32]| {
+                for (i, x) in part.iter_mut().enumerate() {
+                    *x = 100 + i as u32;
+                }
+            };
+
+            std::thread::scope(|s| {
+                s.spawn(|| fill_left(&mut left));
+                s.spawn(|| fill_right(&mut right));
+            });
+
+            let whole = left.recombine(right).map_err(|_| ()).unwrap();
+            assert_eq!((whole.offset(), whole.len()), (0, 100));
+        }
+
+        let data = buffer.into_inner();
+        assert!(data[..40].iter().all(|x| *x == 1));
+        assert!(data[40..]
+            .iter()
+            .enumerate()
+            .all(|(i, x)| *x == 100 + i as u32));
+    }
+
+    #[test]
+    fn test_recombine_checks_adjacency() {
+        let mut buffer = SplittableBuffer::new(vec![0u8; 10]);
+        let (a, rest) = buffer.split_at_mut(3);
+        let (b, c) = rest.split_at_mut(3);
+        assert_eq!((b.offset(), c.offset()), (3, 6));
+
+        // Not adjacent, or in the wrong order.
+        let (a, c) = a.recombine(c).unwrap_err();
+        let (b, a) = b.recombine(a).unwrap_err();
+
+        let ab = a.recombine(b).map_err(|_| ()).unwrap();
+        let abc = ab.recombine(c).map_err(|_| ()).unwrap();
+        assert_eq!(abc.len(), 10);
+    }
+
+    #[test]
+    fn test_recombine_checks_buffer() {
+        let mut first = SplittableBuffer::new(vec![0u8; 10]);
+        let mut second = SplittableBuffer::new(vec![0u8; 10]);
+        let (a, _) = first.split_at_mut(5);
+        let (_, b) = second.split_at_mut(5);
+        assert!(a.recombine(b).is_err());
+    }
+}
@@ -0,0 +1,207 @@
+//! A state-variable filter using the topology-preserving transform.
+//!
+//! Unlike [crate::biquad], a state variable filter produces lowpass, bandpass, highpass, and notch outputs at once, and
+//! stays stable when the cutoff is modulated quickly.  This is the "trapezoidal integrator" form from Andrew Simper's
+//! Cytomic technical papers.
+use std::f64::consts::PI;
+
+use crate::config::*;
+
+/// All of the outputs of a [StateVariableFilter] for one sample.
+///
+/// For any input, `low + band / q + high` is the input.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SvfOutputs {
+    pub low: f64,
+    pub band: f64,
+    pub high: f64,
+    pub notch: f64,
+}
+
+/// A 1-channel state-variable filter.
+#[derive(Debug, Clone)]
+pub struct StateVariableFilter {
+    // The coefficients.
+    g: f64,
+    k: f64,
+    a1: f64,
+    a2: f64,
+    a3: f64,
+
+    // The states of the two integrators.
+    ic1eq: f64,
+    ic2eq: f64,
+}
+
+impl StateVariableFilter {
+    /// Create a filter with a cutoff in hz and a resonance given as `Q`.
+    ///
+    /// [crate::biquad::DEFAULT_Q] gives a butterworth response from the lowpass and highpass outputs.
+    pub fn new(cutoff: f64, q: f64) -> StateVariableFilter {
+        let mut ret = StateVariableFilter {
+            g: 0.0,
+            k: 0.0,
+            a1: 0.0,
+            a2: 0.0,
+            a3: 0.0,
+            ic1eq: 0.0,
+            ic2eq: 0.0,
+        };
+        ret.set_resonance(q);
+        ret.set_cutoff(cutoff);
+        ret
+    }
+
+    fn update_coefficients(&mut self) {
+        self.a1 = 1.0 / (1.0 + self.g * (self.g + self.k));
+        self.a2 = self.g * self.a1;
+        self.a3 = self.g * self.a2;
+    }
+
+    /// Set the cutoff, in hz.
+    ///
+    /// This costs one `tan` and a handful of arithmetic, and so may be called every sample.  The cutoff is clamped to
+    /// just below nyquist.
+    pub fn set_cutoff(&mut self, cutoff: f64) {
+        let nyquist = SR as f64 / 2.0;
+        let cutoff = cutoff.clamp(0.0, nyquist * 0.999);
+        self.g = (PI * cutoff / SR as f64).tan();
+        self.update_coefficients();
+    }
+
+    /// Set the resonance, as `Q`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `q` is not positive.
+    pub fn set_resonance(&mut self, q: f64) {
+        assert!(q > 0.0, "Q must be positive");
+        self.k = 1.0 / q;
+        self.update_coefficients();
+    }
+
+    /// Clear the state of this filter.
+    pub fn reset(&mut self) {
+        self.ic1eq = 0.0;
+        self.ic2eq = 0.0;
+    }
+
+    /// Tick this filter by one sample, returning all of the outputs.
+    pub fn tick(&mut self, input: f64) -> SvfOutputs {
+        let v3 = input - self.ic2eq;
+        let v1 = self.a1 * self.ic1eq + self.a2 * v3;
+        let v2 = self.ic2eq + self.a2 * self.ic1eq + self.a3 * v3;
+        self.ic1eq = 2.0 * v1 - self.ic1eq;
+        self.ic2eq = 2.0 * v2 - self.ic2eq;
+
+        let low = v2;
+        let band = v1;
+        let high = input - self.k * band - low;
+        SvfOutputs {
+            low,
+            band,
+            high,
+            notch: low + high,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::biquad::DEFAULT_Q;
+    use crate::close_floats::*;
+    use crate::fast_xoroshiro::FastXoroshiro128PlusPlus;
+    use crate::goertzel::Goertzel;
+
+    fn test_input(i: usize) -> f64 {
+        (i as f64 * 0.05).sin() + 0.5 * (i as f64 * 0.9).sin()
+    }
+
+    /// Drive the filter with a unit sine at `freq` and measure the magnitude of each output, in the order low, band,
+    /// high, notch.
+    fn response(cutoff: f64, q: f64, freq: f64) -> [f64; 4] {
+        // 4410-sample blocks put Goertzel bins every 10 hz.
+        const BLOCK: usize = 4410;
+
+        let mut filt = StateVariableFilter::new(cutoff, q);
+        let mut detectors = [(); 4].map(|_| Goertzel::new(freq, SR as u32, BLOCK));
+        let omega = 2.0 * PI * freq / SR as f64;
+
+        // Give the filter a second to settle, then measure one block.
+        let settle = SR as usize;
+        for i in 0..settle + BLOCK {
+            let out = filt.tick((omega * i as f64).sin());
+            if i >= settle {
+                for (d, o) in detectors
+                    .iter_mut()
+                    .zip([out.low, out.band, out.high, out.notch])
+                {
+                    d.process(&[o]);
+                }
+            }
+        }
+
+        detectors.map(|d| d.magnitude())
+    }
+
+    #[test]
+    fn test_response_at_cutoff() {
+        // With a butterworth Q, lowpass and highpass are both 3 db down at the cutoff, and the notch is at its deepest.
+        let [low, _, high, notch] = response(1000.0, DEFAULT_Q, 1000.0);
+        close_floats64(low, std::f64::consts::FRAC_1_SQRT_2, 1e-3);
+        close_floats64(high, std::f64::consts::FRAC_1_SQRT_2, 1e-3);
+        assert!(notch < 1e-3, "{}", notch);
+
+        // The bandpass peaks at the cutoff with a gain of Q.
+        for q in [DEFAULT_Q, 2.0, 5.0] {
+            let peak = response(1000.0, q, 1000.0)[1];
+            close_floats64(peak, q, 1e-3 * q);
+            for other in [500.0, 2000.0] {
+                assert!(response(1000.0, q, other)[1] < peak);
+            }
+        }
+    }
+
+    #[test]
+    fn test_stopbands() {
+        // Both slopes are 12 db per octave, so a decade past the cutoff should be about 40 db down.
+        let high = response(1000.0, DEFAULT_Q, 100.0)[2];
+        assert!(high < 0.02, "{}", high);
+        let low = response(1000.0, DEFAULT_Q, 10000.0)[0];
+        assert!(low < 0.02, "{}", low);
+
+        // And the passbands are near unity.
+        close_floats64(response(1000.0, DEFAULT_Q, 100.0)[0], 1.0, 1e-2);
+        close_floats64(response(1000.0, DEFAULT_Q, 10000.0)[2], 1.0, 1e-2);
+    }
+
+    #[test]
+    fn test_dc_response() {
+        let mut filt = StateVariableFilter::new(1000.0, DEFAULT_Q);
+        let mut out = SvfOutputs::default();
+        for _ in 0..10000 {
+            out = filt.tick(0.5);
+        }
+
+        close_floats64(out.low, 0.5, 1e-6);
+        close_floats64(out.band, 0.0, 1e-6);
+        close_floats64(out.high, 0.0, 1e-6);
+    }
+
+    #[test]
+    fn test_stable_under_modulation() {
+        let mut rng = FastXoroshiro128PlusPlus::<1>::new_seeded(5);
+        let mut filt = StateVariableFilter::new(1000.0, 5.0);
+
+        for i in 0..100000 {
+            let cutoff = 20.0 + (rng.gen_u64() % 20000) as f64;
+            filt.set_cutoff(cutoff);
+            let out = filt.tick(test_input(i));
+            for o in [out.low, out.band, out.high, out.notch] {
+                assert!(o.is_finite() && o.abs() < 100.0, "{:?}", out);
+            }
+        }
+    }
+}
@@ -0,0 +1,207 @@
+//! Window functions.
+//!
+//! All of the windows here are symmetric: the first and last samples are the same, as is appropriate for filter design.
+//! Windows of length 0 are empty, and windows of length 1 are `[1.0]`.
+use std::f64::consts::PI;
+
+/// Build a window of length `n` from a function of `i / (n - 1)`, which ranges over `0.0..=1.0`.
+fn build_window(n: usize, mut f: impl FnMut(f64) -> f64) -> Vec<f64> {
+    match n {
+        0 => return vec![],
+        1 => return vec![1.0],
+        _ => (),
+    }
+
+    let denom = (n - 1) as f64;
+    (0..n).map(|i| f(i as f64 / denom)).collect()
+}
+
+/// A Hann window, which starts and ends at 0.
+pub fn hann(n: usize) -> Vec<f64> {
+    build_window(n, |x| 0.5 - 0.5 * (2.0 * PI * x).cos())
+}
+
+/// A Hamming window.
+pub fn hamming(n: usize) -> Vec<f64> {
+    build_window(n, |x| 0.54 - 0.46 * (2.0 * PI * x).cos())
+}
+
+/// A Blackman window, using the common approximation with coefficients 0.42, 0.5, and 0.08.
+pub fn blackman(n: usize) -> Vec<f64> {
+    build_window(n, |x| {
+        0.42 - 0.5 * (2.0 * PI * x).cos() + 0.08 * (4.0 * PI * x).cos()
+    })
+}
+
+/// A Kaiser window.
+///
+/// `beta` trades main lobe width for sidelobe level.  A `beta` of 0 is a rectangular window, and higher values narrow
+/// the window.
+///
+/// # Panics
+///
+/// Panics if `beta` is negative or not finite.
+pub fn kaiser(n: usize, beta: f64) -> Vec<f64> {
+    assert!(
+        beta.is_finite() && beta >= 0.0,
+        "beta must be finite and non-negative"
+    );
+
+    // I0 overflows f64 for arguments past about 700, so work with the scaled form and put the exponentials back as
+    // one factor which is at most 1.
+    let denom = bessel_i0_scaled(beta);
+    build_window(n, |x| {
+        let t = 2.0 * x - 1.0;
+        let r = (1.0 - t * t).max(0.0).sqrt();
+        bessel_i0_scaled(beta * r) / denom * (beta * (r - 1.0)).exp()
+    })
+}
+
+/// `I0(x) * e^-x`, where `I0` is the zeroth-order modified Bessel function of the first kind and `x >= 0`.
+fn bessel_i0_scaled(x: f64) -> f64 {
+    // Past this, the power series' terms get too large and the asymptotic expansion is already accurate to well under
+    // f64 precision.
+    const ASYMPTOTIC_THRESHOLD: f64 = 500.0;
+
+    if x < ASYMPTOTIC_THRESHOLD {
+        return bessel_i0_series(x) * (-x).exp();
+    }
+
+    // I0(x) e^-x ~ 1 / sqrt(2 pi x) * sum(((2k - 1)!!)^2 / (k! (8x)^k))
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    for k in 1..=6 {
+        let odd = (2 * k - 1) as f64;
+        term *= odd * odd / (k as f64 * 8.0 * x);
+        sum += term;
+    }
+    sum / (2.0 * PI * x).sqrt()
+}
+
+/// The zeroth-order modified Bessel function of the first kind, evaluated by its power series.
+///
+/// Only used for arguments small enough that the result doesn't overflow.
+fn bessel_i0_series(x: f64) -> f64 {
+    // The terms peak around k = x / 2 and are negligible well before k = x, so this bound is never reached for the
+    // arguments we are given; it only guarantees termination.
+    const MAX_TERMS: usize = 2000;
+
+    let half_x = x / 2.0;
+    let mut sum = 1.0;
+    let mut term = 1.0;
+
+    for k in 1..MAX_TERMS {
+        let k = k as f64;
+        term *= (half_x / k) * (half_x / k);
+        if term.is_nan() || term < sum * 1e-17 {
+            break;
+        }
+        sum += term;
+    }
+
+    sum
+}
+
+/// Multiply some samples by a window, in place.
+///
+/// # Panics
+///
+/// Panics if the samples and window are not the same length.
+pub fn apply_window(samples: &mut [f64], window: &[f64]) {
+    assert_eq!(samples.len(), window.len());
+    for (s, w) in samples.iter_mut().zip(window.iter()) {
+        *s *= *w;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::close_floats::*;
+
+    fn check_symmetric(window: &[f64]) {
+        for (a, b) in window.iter().zip(window.iter().rev()) {
+            close_floats64(*a, *b, 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_symmetry_and_sums() {
+        for n in [5usize, 64, 127] {
+            let nf = n as f64;
+
+            let w = hann(n);
+            assert_eq!(w.len(), n);
+            check_symmetric(&w);
+            close_floats64(w.iter().sum(), (nf - 1.0) / 2.0, 1e-9);
+
+            let w = hamming(n);
+            check_symmetric(&w);
+            close_floats64(w.iter().sum(), 0.54 * nf - 0.46, 1e-9);
+
+            let w = blackman(n);
+            check_symmetric(&w);
+            close_floats64(w.iter().sum(), 0.42 * nf - 0.42, 1e-9);
+
+            let w = kaiser(n, 8.6);
+            check_symmetric(&w);
+            assert!(w.iter().all(|x| (0.0..=1.0).contains(x)));
+
+            // Beta of 0 is rectangular.
+            close_floats64(kaiser(n, 0.0).iter().sum(), nf, 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_hann_endpoints() {
+        let w = hann(33);
+        close_floats64(w[0], 0.0, 1e-12);
+        close_floats64(w[32], 0.0, 1e-12);
+        close_floats64(w[16], 1.0, 1e-12);
+    }
+
+    #[test]
+    fn test_bessel_i0() {
+        close_floats64(bessel_i0_series(0.0), 1.0, 1e-15);
+        close_floats64(bessel_i0_series(1.0), 1.2660658777520082, 1e-14);
+        close_floats64(bessel_i0_series(10.0), 2815.716628466254, 1e-9);
+
+        // The series and the asymptotic expansion agree where they meet.
+        let series = bessel_i0_series(500.0) * (-500.0f64).exp();
+        close_floats64(series / bessel_i0_scaled(500.0), 1.0, 1e-12);
+    }
+
+    #[test]
+    fn test_kaiser_large_beta() {
+        for beta in [800.0, 1e6] {
+            let w = kaiser(65, beta);
+            check_symmetric(&w);
+            assert!(w.iter().all(|x| (0.0..=1.0).contains(x)), "{:?}", w);
+            close_floats64(w[32], 1.0, 1e-12);
+            close_floats64(w[0], 0.0, 1e-12);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_kaiser_nan_beta() {
+        kaiser(5, f64::NAN);
+    }
+
+    #[test]
+    fn test_degenerate_lengths() {
+        assert!(hann(0).is_empty());
+        assert_eq!(hann(1), vec![1.0]);
+        assert_eq!(kaiser(1, 5.0), vec![1.0]);
+    }
+
+    #[test]
+    fn test_apply_window() {
+        let mut samples = vec![2.0; 5];
+        apply_window(&mut samples, &hann(5));
+        for (got, expected) in samples.iter().zip([0.0, 1.0, 2.0, 1.0, 0.0]) {
+            close_floats64(*got, expected, 1e-12);
+        }
+    }
+}